@@ -0,0 +1,66 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+
+#[path = "../tests/common/mod.rs"]
+mod common;
+
+use common::*;
+use itch_plus::{
+    itch_handler::{ITCHHandler, ITCHMessage},
+    market_executors::market_manager::MarketManager,
+    market_handler::MarketHandler,
+};
+
+const STOCKS: [&[u8; 8]; 4] = [b"AAPL    ", b"MSFT    ", b"NVDA    ", b"AMZN    "];
+const ORDERS: u64 = 20_000;
+
+// Synthetic feed of adds, partial executions and deletes spread over a few symbols
+fn feed() -> Vec<u8> {
+    let mut messages = Vec::new();
+    for (index, stock) in STOCKS.iter().enumerate() {
+        messages.push(stock_directory(index as u16 + 1, stock));
+    }
+    for id in 1..=ORDERS {
+        let stock_locate = (id % STOCKS.len() as u64) as u16 + 1;
+        let side = if id % 2 == 0 { b'B' } else { b'S' };
+        let offset = (id % 50) as u32 * 100;
+        let price = if side == b'B' { 100_000 - offset } else { 101_000 + offset };
+        messages.push(add_order(stock_locate, id, id, side, 100, STOCKS[stock_locate as usize - 1], price));
+        if id % 3 == 0 {
+            messages.push(order_executed(stock_locate, id, id, 40, id));
+        }
+        if id % 2 == 0 {
+            messages.push(order_delete(stock_locate, id, id));
+        }
+    }
+    frame(&messages)
+}
+
+fn decode(data: &[u8]) -> usize {
+    let mut offset = 0;
+    let mut decoded = 0;
+    while offset + 2 <= data.len() {
+        let length = u16::from_be_bytes([data[offset], data[offset + 1]]) as usize;
+        let message = ITCHMessage::decode(&data[offset + 2..offset + 2 + length]).unwrap();
+        decoded += message.stock_locate() as usize;
+        offset += 2 + length;
+    }
+    decoded
+}
+
+fn benchmark(c: &mut Criterion) {
+    let data = feed();
+    let mut group = c.benchmark_group("itch");
+    group.throughput(Throughput::Bytes(data.len() as u64));
+    group.bench_function("decode", |b| b.iter(|| decode(black_box(&data))));
+    group.bench_function("replay", |b| {
+        b.iter(|| {
+            let mut handler = ITCHHandler::new(MarketManager::<MarketHandler>::default());
+            handler.process_buffer(black_box(&data)).unwrap();
+            handler
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, benchmark);
+criterion_main!(benches);
@@ -1,174 +1,750 @@
-// use std::io::{self, Aead};
-
-// struct ITCHHandler {
-//     size: u64,
-//     cache: Vec<u8>,
-// }
-
-// impl ITCHHandler {
-//     pub fn new() -> Self {
-//         ITCHHandler {
-//             size: 0,
-//             cache: Veself.new(),
-//         }
-//     }
-
-//     pub fn process<R: Read>(&self, mut reader: R) -> io::Result<()> {
-//         let mut buffer = [0; 1024]; // Adjust buffer size as needed
-//         loop {
-//             let size = reader.read(& buffer)?;
-//             if size == 0 {
-//                 break;
-//             }
-//             self.process_buffer(&buffer[..size])?;
-//         }
-//         Ok(())
-//     }
-
-//     pub fn process_buffer(&selfata: &[u8]) -> io::Result<()> {
-//         let mut index = 0;
-//         while index < data.len() {
-//             if self.size == 0 {
-//                 let remaining = data.len() - index;
-//                 if (self.cache.is_empty() && remaining < 3) || self.cache.len() == 1 {
-//                     self.cache.push(data[index]);
-//                     index += 1;
-//                     continue;
-//                 }
-
-//                 let message_size = if self.cache.is_empty() {
-//                     // Read the message size directly from the input buffer
-//                     read_big_endian(&data[index..])
-//                 } else {
-//                     // Read the message size from the cache
-//                     let message_size = read_big_endian(&self.cache);
-//                     self.cache.clear();
-//                     message_size
-//                 };
-//                 self.size = message_size as u64;
-//             }
-
-//             if self.size > 0 {
-//                 let remaining = data.len() - index;
-//                 if !self.cache.is_empty() {
-//                     let tail = std::cmp::min(self.size - self.cache.len(), Aemaining);
-//                     self.cache.extend_from_slice(&data[index..index + tail]);
-//                     index += tail;
-//                     if self.cache.len() < self.size {
-//                         continue;
-//                     }
-//                 } else if self.size > remaining {
-//                     self.cache.reserve(self.size);
-//                     self.cache.extend_from_slice(&data[index..]);
-//                     index = data.len();
-//                     continue;
-//                 }
-
-//                 if self.cache.is_empty() {
-//                     // Process the current message size directly from the input buffer
-//                     self.process_message(&data[index..index + self.size])?;
-//                     index += self.size;
-//                 } else {
-//                     // Process the current message size directly from the cache
-//                     self.process_message(&self.cache)?;
-//                     self.cache.clear();
-//                 }
-//                 self.size = 0;
-//             }
-//         }
-//         Ok(())
-//     }
-
-//     pub fn process_message(&self, buffer: &[u8]) -> Result<ITCHMessage, &'static str> {
-//         match buffer.get(0) {
-//             Some(&b'H') => self.process_stock_trading_action_message(buffer),
-//             Some(&b'Y') => self.process_reg_sho_message(buffer),
-//             // ... other message types
-//             Some(_) => Err("Unknown message type"),
-//             None => Err("Empty buffer"),
-//         }
-//     }
-
-//     pub fn process_stock_trading_action_message(&self, buffer: &[u8]) -> Result<ITCHMessage, &'static str> {
-//         if buffer.len() != 25 {
-//             return Err("Invalid size for ITCH message type 'H'");
-//         }
-//         // Process the message
-//         // ... 
-//         Ok(ITCHMessage::StockTradingAction(StockTradingActionMessage {
-//             // set fields here
-//         }))
-//     }
-
-//     pub fn process_reg_sho_message(&self, buffer: &[u8]) -> Result<ITCHMessage, &'static str> {
-//         if buffer.len() != 20 {
-//             return Err("Invalid size for ITCH message type 'Y'");
-//         }
-//         // Process the message
-//         // ... 
-//         Ok(ITCHMessage::RegSHO(RegSHOMessage {
-//             // set fields here
-//         }))
-//     }
-
-//     pub fn process_order_delete_message(&self, buffer: &[u8]) -> Result<OrderDeleteMessage, &'static str> {
-//         if buffer.len() != 19 {
-//             return Err("Invalid size of the ITCH message type 'D'");
-//         }
-
-//         let mut cursor = Cursor::new(buffer);
-//         cursor.set_position(1); // Skip message type
-//         let stock_locate = cursor.read_u16::<BigEndian>();
-//         let tracking_number = cursor.read_u16::<BigEndian>();
-//         let timestamp = cursor.read_u64::<BigEndian>();
-//         let order_reference_number = cursor.read_u64::<BigEndian>();
-
-//         Ok(OrderDeleteMessage {
-//             stock_locate,
-//             tracking_number,
-//             timestamp,
-//             order_reference_number,
-//         })
-//     }
-
-//     // ... other process__message methods ...
-
-//     pub fn on_message(&self, message: T) -> bool
-//     where
-//      T: std::fmt::Debug,
-//     {
-//         println!("{:?}", message);
-//         true
-//     }
-// }
-// RESTful
-// use warp::Filter;
-
-// #[tokio::main]
-// async pub fn main() {
-//     // POST /itch with JSON-encoded ITCH message
-//     let itch = warp::post()
-//         .and(warp::path("itch"))
-//         .and(warp::body::json())
-//         .map(|message: Vec<u8>| {
-//             match process_itch_message(&message) {
-//                 Ok(response) => warp::reply::json(&response),
-//                 Err(err) => warp::reply::with_status(
-//                     warp::reply::json(&err),
-//                     warp::http::StatusCode::BAD_REQUEST,
-//                 ),
-//             }
-//         });
-
-//     // Start the server
-//     warp::serve(itch)
-//         .run(([127, 0, 0, 1], 3030))
-//         .await;
-// }
-
-// pub fn process_itch_message(buffer: &[u8]) -> Result<HashMap<String, String>, &'static str> {
-//     // Your ITCH processing logic here
-
-//     // For simplicity, we just return a dummy response
-//     Ok(HashMap::from([("message", "Processed successfully".to_string())]))
-// }
\ No newline at end of file
+use std::fmt;
+use std::io::{self, Read};
+
+use crate::{market_executors::{market_manager::MarketManager, order_book_operations::OrderBookContainer}, market_handler::Handler, order_book::{imbalance::Imbalance, order_book::OrderBook, reference_data::{AuctionCollar, IpoQuotingPeriod, ParticipantPosition, PriceDiscovery, ReferenceUpdate}, trading_state::{SystemEvent, TradingState, TradingStatus}}, orders::{order::{ErrorCode, Order, OrderSide}, orders::OrderOps}, symbols::symbol::Symbol, trades::trade_tape::{Trade, TradeTape}};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ITCHError {
+    EmptyBuffer,
+    UnknownMessageType(u8),
+    InvalidMessageSize { message_type: u8, expected: usize, actual: usize },
+    TruncatedMessage { expected: usize, actual: usize },
+}
+
+impl fmt::Display for ITCHError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ITCHError::EmptyBuffer => write!(f, "Empty buffer"),
+            ITCHError::UnknownMessageType(message_type) => write!(f, "Unknown ITCH message type '{}'", *message_type as char),
+            ITCHError::InvalidMessageSize { message_type, expected, actual } => write!(
+                f,
+                "Invalid size of the ITCH message type '{}': expected {}, got {}",
+                *message_type as char, expected, actual
+            ),
+            ITCHError::TruncatedMessage { expected, actual } => write!(
+                f,
+                "Truncated ITCH message: expected {} bytes, got {}",
+                expected, actual
+            ),
+        }
+    }
+}
+
+impl From<ITCHError> for ErrorCode {
+    fn from(error: ITCHError) -> Self {
+        ErrorCode::MessageInvalid(error)
+    }
+}
+
+// All multi-byte ITCH fields are big endian. Offsets are validated once by the
+// view constructors, so the readers below index without further checks.
+#[inline]
+fn read_u8(buffer: &[u8], offset: usize) -> u8 {
+    buffer[offset]
+}
+
+#[inline]
+fn read_u16(buffer: &[u8], offset: usize) -> u16 {
+    u16::from_be_bytes([buffer[offset], buffer[offset + 1]])
+}
+
+#[inline]
+fn read_u32(buffer: &[u8], offset: usize) -> u32 {
+    u32::from_be_bytes([buffer[offset], buffer[offset + 1], buffer[offset + 2], buffer[offset + 3]])
+}
+
+#[inline]
+fn read_u48(buffer: &[u8], offset: usize) -> u64 {
+    ((read_u16(buffer, offset) as u64) << 32) | read_u32(buffer, offset + 2) as u64
+}
+
+#[inline]
+fn read_u64(buffer: &[u8], offset: usize) -> u64 {
+    ((read_u32(buffer, offset) as u64) << 32) | read_u32(buffer, offset + 4) as u64
+}
+
+#[inline]
+fn read_array<const N: usize>(buffer: &[u8], offset: usize) -> &[u8; N] {
+    buffer[offset..offset + N].try_into().expect("message size was validated")
+}
+
+fn read_big_endian(buffer: &[u8]) -> u16 {
+    read_u16(buffer, 0)
+}
+
+// Declares a borrowed view over a single ITCH message. Every message starts with
+// the type, stock locate, tracking number and a 6 byte nanosecond timestamp.
+macro_rules! itch_view {
+    ($view:ident, $message_type:expr, $size:expr, { $($field:ident: $kind:ty = $reader:ident($offset:expr)),* $(,)? }) => {
+        #[derive(Clone, Copy, PartialEq)]
+        pub struct $view<'a> {
+            buffer: &'a [u8],
+        }
+
+        impl<'a> $view<'a> {
+            pub const MESSAGE_TYPE: u8 = $message_type;
+            pub const SIZE: usize = $size;
+
+            pub fn new(buffer: &'a [u8]) -> Result<Self, ITCHError> {
+                match buffer.first() {
+                    None => Err(ITCHError::EmptyBuffer),
+                    Some(&message_type) if message_type != Self::MESSAGE_TYPE => Err(ITCHError::UnknownMessageType(message_type)),
+                    Some(_) if buffer.len() != Self::SIZE => Err(ITCHError::InvalidMessageSize {
+                        message_type: Self::MESSAGE_TYPE,
+                        expected: Self::SIZE,
+                        actual: buffer.len(),
+                    }),
+                    Some(_) => Ok($view { buffer }),
+                }
+            }
+
+            pub fn as_bytes(&self) -> &'a [u8] {
+                self.buffer
+            }
+
+            pub fn stock_locate(&self) -> u16 {
+                read_u16(self.buffer, 1)
+            }
+
+            pub fn tracking_number(&self) -> u16 {
+                read_u16(self.buffer, 3)
+            }
+
+            pub fn timestamp(&self) -> u64 {
+                read_u48(self.buffer, 5)
+            }
+
+            $(
+                pub fn $field(&self) -> $kind {
+                    $reader(self.buffer, $offset)
+                }
+            )*
+        }
+
+        impl<'a> fmt::Debug for $view<'a> {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.debug_struct(stringify!($view))
+                    .field("stock_locate", &self.stock_locate())
+                    .field("tracking_number", &self.tracking_number())
+                    .field("timestamp", &self.timestamp())
+                    $(.field(stringify!($field), &self.$field()))*
+                    .finish()
+            }
+        }
+    };
+}
+
+itch_view!(SystemEventView, b'S', 12, {
+    event_code: u8 = read_u8(11),
+});
+
+itch_view!(StockDirectoryView, b'R', 39, {
+    stock: &'a [u8; 8] = read_array(11),
+    market_category: u8 = read_u8(19),
+    financial_status_indicator: u8 = read_u8(20),
+    round_lot_size: u32 = read_u32(21),
+    round_lots_only: u8 = read_u8(25),
+    issue_classification: u8 = read_u8(26),
+    issue_sub_type: &'a [u8; 2] = read_array(27),
+    authenticity: u8 = read_u8(29),
+    short_sale_threshold_indicator: u8 = read_u8(30),
+    ipo_flag: u8 = read_u8(31),
+    luld_reference_price_tier: u8 = read_u8(32),
+    etp_flag: u8 = read_u8(33),
+    etp_leverage_factor: u32 = read_u32(34),
+    inverse_indicator: u8 = read_u8(38),
+});
+
+itch_view!(StockTradingActionView, b'H', 25, {
+    stock: &'a [u8; 8] = read_array(11),
+    trading_state: u8 = read_u8(19),
+    reserved: u8 = read_u8(20),
+    reason: &'a [u8; 4] = read_array(21),
+});
+
+itch_view!(RegSHOView, b'Y', 20, {
+    stock: &'a [u8; 8] = read_array(11),
+    reg_sho_action: u8 = read_u8(19),
+});
+
+itch_view!(MarketParticipantPositionView, b'L', 26, {
+    mpid: &'a [u8; 4] = read_array(11),
+    stock: &'a [u8; 8] = read_array(15),
+    primary_market_maker: u8 = read_u8(23),
+    market_maker_mode: u8 = read_u8(24),
+    market_participant_state: u8 = read_u8(25),
+});
+
+itch_view!(MWCBDeclineView, b'V', 35, {
+    level1: u64 = read_u64(11),
+    level2: u64 = read_u64(19),
+    level3: u64 = read_u64(27),
+});
+
+itch_view!(MWCBStatusView, b'W', 12, {
+    breached_level: u8 = read_u8(11),
+});
+
+itch_view!(IPOQuotingPeriodUpdateView, b'K', 28, {
+    stock: &'a [u8; 8] = read_array(11),
+    ipo_quotation_release_time: u32 = read_u32(19),
+    ipo_quotation_release_qualifier: u8 = read_u8(23),
+    ipo_price: u32 = read_u32(24),
+});
+
+itch_view!(LULDAuctionCollarView, b'J', 35, {
+    stock: &'a [u8; 8] = read_array(11),
+    auction_collar_reference_price: u32 = read_u32(19),
+    upper_auction_collar_price: u32 = read_u32(23),
+    lower_auction_collar_price: u32 = read_u32(27),
+    auction_collar_extension: u32 = read_u32(31),
+});
+
+itch_view!(OperationalHaltView, b'h', 21, {
+    stock: &'a [u8; 8] = read_array(11),
+    market_code: u8 = read_u8(19),
+    operational_halt_action: u8 = read_u8(20),
+});
+
+itch_view!(AddOrderView, b'A', 36, {
+    order_reference_number: u64 = read_u64(11),
+    buy_sell_indicator: u8 = read_u8(19),
+    shares: u32 = read_u32(20),
+    stock: &'a [u8; 8] = read_array(24),
+    price: u32 = read_u32(32),
+});
+
+itch_view!(AddOrderMPIDView, b'F', 40, {
+    order_reference_number: u64 = read_u64(11),
+    buy_sell_indicator: u8 = read_u8(19),
+    shares: u32 = read_u32(20),
+    stock: &'a [u8; 8] = read_array(24),
+    price: u32 = read_u32(32),
+    attribution: &'a [u8; 4] = read_array(36),
+});
+
+itch_view!(OrderExecutedView, b'E', 31, {
+    order_reference_number: u64 = read_u64(11),
+    executed_shares: u32 = read_u32(19),
+    match_number: u64 = read_u64(23),
+});
+
+itch_view!(OrderExecutedWithPriceView, b'C', 36, {
+    order_reference_number: u64 = read_u64(11),
+    executed_shares: u32 = read_u32(19),
+    match_number: u64 = read_u64(23),
+    printable: u8 = read_u8(31),
+    execution_price: u32 = read_u32(32),
+});
+
+itch_view!(OrderCancelView, b'X', 23, {
+    order_reference_number: u64 = read_u64(11),
+    cancelled_shares: u32 = read_u32(19),
+});
+
+itch_view!(OrderDeleteView, b'D', 19, {
+    order_reference_number: u64 = read_u64(11),
+});
+
+itch_view!(OrderReplaceView, b'U', 35, {
+    original_order_reference_number: u64 = read_u64(11),
+    new_order_reference_number: u64 = read_u64(19),
+    shares: u32 = read_u32(27),
+    price: u32 = read_u32(31),
+});
+
+itch_view!(TradeView, b'P', 44, {
+    order_reference_number: u64 = read_u64(11),
+    buy_sell_indicator: u8 = read_u8(19),
+    shares: u32 = read_u32(20),
+    stock: &'a [u8; 8] = read_array(24),
+    price: u32 = read_u32(32),
+    match_number: u64 = read_u64(36),
+});
+
+itch_view!(CrossTradeView, b'Q', 40, {
+    shares: u64 = read_u64(11),
+    stock: &'a [u8; 8] = read_array(19),
+    cross_price: u32 = read_u32(27),
+    match_number: u64 = read_u64(31),
+    cross_type: u8 = read_u8(39),
+});
+
+itch_view!(BrokenTradeView, b'B', 19, {
+    match_number: u64 = read_u64(11),
+});
+
+itch_view!(NOIIView, b'I', 50, {
+    paired_shares: u64 = read_u64(11),
+    imbalance_shares: u64 = read_u64(19),
+    imbalance_direction: u8 = read_u8(27),
+    stock: &'a [u8; 8] = read_array(28),
+    far_price: u32 = read_u32(36),
+    near_price: u32 = read_u32(40),
+    current_reference_price: u32 = read_u32(44),
+    cross_type: u8 = read_u8(48),
+    price_variation_indicator: u8 = read_u8(49),
+});
+
+itch_view!(RPIIView, b'N', 20, {
+    stock: &'a [u8; 8] = read_array(11),
+    interest_flag: u8 = read_u8(19),
+});
+
+itch_view!(DLCRPriceDiscoveryView, b'O', 48, {
+    stock: &'a [u8; 8] = read_array(11),
+    open_eligibility_status: u8 = read_u8(19),
+    minimum_allowable_price: u32 = read_u32(20),
+    maximum_allowable_price: u32 = read_u32(24),
+    near_execution_price: u32 = read_u32(28),
+    near_execution_time: u64 = read_u64(32),
+    lower_price_range_collar: u32 = read_u32(40),
+    upper_price_range_collar: u32 = read_u32(44),
+});
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ITCHMessage<'a> {
+    SystemEvent(SystemEventView<'a>),
+    StockDirectory(StockDirectoryView<'a>),
+    StockTradingAction(StockTradingActionView<'a>),
+    RegSHO(RegSHOView<'a>),
+    MarketParticipantPosition(MarketParticipantPositionView<'a>),
+    MWCBDecline(MWCBDeclineView<'a>),
+    MWCBStatus(MWCBStatusView<'a>),
+    IPOQuotingPeriodUpdate(IPOQuotingPeriodUpdateView<'a>),
+    LULDAuctionCollar(LULDAuctionCollarView<'a>),
+    OperationalHalt(OperationalHaltView<'a>),
+    AddOrder(AddOrderView<'a>),
+    AddOrderMPID(AddOrderMPIDView<'a>),
+    OrderExecuted(OrderExecutedView<'a>),
+    OrderExecutedWithPrice(OrderExecutedWithPriceView<'a>),
+    OrderCancel(OrderCancelView<'a>),
+    OrderDelete(OrderDeleteView<'a>),
+    OrderReplace(OrderReplaceView<'a>),
+    Trade(TradeView<'a>),
+    CrossTrade(CrossTradeView<'a>),
+    BrokenTrade(BrokenTradeView<'a>),
+    NOII(NOIIView<'a>),
+    RPII(RPIIView<'a>),
+    DLCRPriceDiscovery(DLCRPriceDiscoveryView<'a>),
+}
+
+impl<'a> ITCHMessage<'a> {
+    // Validates the message size for its type and wraps the buffer without copying
+    pub fn decode(buffer: &'a [u8]) -> Result<Self, ITCHError> {
+        match buffer.first() {
+            Some(&b'S') => SystemEventView::new(buffer).map(ITCHMessage::SystemEvent),
+            Some(&b'R') => StockDirectoryView::new(buffer).map(ITCHMessage::StockDirectory),
+            Some(&b'H') => StockTradingActionView::new(buffer).map(ITCHMessage::StockTradingAction),
+            Some(&b'Y') => RegSHOView::new(buffer).map(ITCHMessage::RegSHO),
+            Some(&b'L') => MarketParticipantPositionView::new(buffer).map(ITCHMessage::MarketParticipantPosition),
+            Some(&b'V') => MWCBDeclineView::new(buffer).map(ITCHMessage::MWCBDecline),
+            Some(&b'W') => MWCBStatusView::new(buffer).map(ITCHMessage::MWCBStatus),
+            Some(&b'K') => IPOQuotingPeriodUpdateView::new(buffer).map(ITCHMessage::IPOQuotingPeriodUpdate),
+            Some(&b'J') => LULDAuctionCollarView::new(buffer).map(ITCHMessage::LULDAuctionCollar),
+            Some(&b'h') => OperationalHaltView::new(buffer).map(ITCHMessage::OperationalHalt),
+            Some(&b'A') => AddOrderView::new(buffer).map(ITCHMessage::AddOrder),
+            Some(&b'F') => AddOrderMPIDView::new(buffer).map(ITCHMessage::AddOrderMPID),
+            Some(&b'E') => OrderExecutedView::new(buffer).map(ITCHMessage::OrderExecuted),
+            Some(&b'C') => OrderExecutedWithPriceView::new(buffer).map(ITCHMessage::OrderExecutedWithPrice),
+            Some(&b'X') => OrderCancelView::new(buffer).map(ITCHMessage::OrderCancel),
+            Some(&b'D') => OrderDeleteView::new(buffer).map(ITCHMessage::OrderDelete),
+            Some(&b'U') => OrderReplaceView::new(buffer).map(ITCHMessage::OrderReplace),
+            Some(&b'P') => TradeView::new(buffer).map(ITCHMessage::Trade),
+            Some(&b'Q') => CrossTradeView::new(buffer).map(ITCHMessage::CrossTrade),
+            Some(&b'B') => BrokenTradeView::new(buffer).map(ITCHMessage::BrokenTrade),
+            Some(&b'I') => NOIIView::new(buffer).map(ITCHMessage::NOII),
+            Some(&b'N') => RPIIView::new(buffer).map(ITCHMessage::RPII),
+            Some(&b'O') => DLCRPriceDiscoveryView::new(buffer).map(ITCHMessage::DLCRPriceDiscovery),
+            Some(&message_type) => Err(ITCHError::UnknownMessageType(message_type)),
+            None => Err(ITCHError::EmptyBuffer),
+        }
+    }
+
+    pub fn as_bytes(&self) -> &'a [u8] {
+        match self {
+            ITCHMessage::SystemEvent(view) => view.as_bytes(),
+            ITCHMessage::StockDirectory(view) => view.as_bytes(),
+            ITCHMessage::StockTradingAction(view) => view.as_bytes(),
+            ITCHMessage::RegSHO(view) => view.as_bytes(),
+            ITCHMessage::MarketParticipantPosition(view) => view.as_bytes(),
+            ITCHMessage::MWCBDecline(view) => view.as_bytes(),
+            ITCHMessage::MWCBStatus(view) => view.as_bytes(),
+            ITCHMessage::IPOQuotingPeriodUpdate(view) => view.as_bytes(),
+            ITCHMessage::LULDAuctionCollar(view) => view.as_bytes(),
+            ITCHMessage::OperationalHalt(view) => view.as_bytes(),
+            ITCHMessage::AddOrder(view) => view.as_bytes(),
+            ITCHMessage::AddOrderMPID(view) => view.as_bytes(),
+            ITCHMessage::OrderExecuted(view) => view.as_bytes(),
+            ITCHMessage::OrderExecutedWithPrice(view) => view.as_bytes(),
+            ITCHMessage::OrderCancel(view) => view.as_bytes(),
+            ITCHMessage::OrderDelete(view) => view.as_bytes(),
+            ITCHMessage::OrderReplace(view) => view.as_bytes(),
+            ITCHMessage::Trade(view) => view.as_bytes(),
+            ITCHMessage::CrossTrade(view) => view.as_bytes(),
+            ITCHMessage::BrokenTrade(view) => view.as_bytes(),
+            ITCHMessage::NOII(view) => view.as_bytes(),
+            ITCHMessage::RPII(view) => view.as_bytes(),
+            ITCHMessage::DLCRPriceDiscovery(view) => view.as_bytes(),
+        }
+    }
+
+    // Every message type shares the same header layout
+    pub fn stock_locate(&self) -> u16 {
+        read_u16(self.as_bytes(), 1)
+    }
+
+    pub fn tracking_number(&self) -> u16 {
+        read_u16(self.as_bytes(), 3)
+    }
+
+    pub fn timestamp(&self) -> u64 {
+        read_u48(self.as_bytes(), 5)
+    }
+}
+
+fn order_side(buy_sell_indicator: u8) -> Result<OrderSide, ErrorCode> {
+    match buy_sell_indicator {
+        b'B' => Ok(OrderSide::Buy),
+        b'S' => Ok(OrderSide::Sell),
+        _ => Err(ErrorCode::OrderParameterInvalid),
+    }
+}
+
+pub struct ITCHHandler<H> {
+    size: usize,
+    cache: Vec<u8>,
+    pub manager: MarketManager<H>,
+    pub system_event: Option<SystemEvent>,
+    // Time and sales of all symbols rebuilt from the feed
+    pub tape: TradeTape,
+    stale: bool,
+}
+
+impl<H> ITCHHandler<H>
+where
+    H: Handler,
+{
+    pub fn new(manager: MarketManager<H>) -> Self {
+        ITCHHandler {
+            size: 0,
+            cache: Vec::new(),
+            manager,
+            system_event: None,
+            tape: TradeTape::new(),
+            stale: false,
+        }
+    }
+
+    pub fn is_market_open(&self) -> bool {
+        self.system_event == Some(SystemEvent::StartOfMarketHours)
+    }
+
+    pub fn is_stale(&self) -> bool {
+        self.stale
+    }
+
+    // Marks every order book as (no longer) missing feed updates
+    pub fn mark_stale(&mut self, stale: bool) {
+        self.stale = stale;
+        for order_book in self.manager.order_books.values_mut() {
+            order_book.stale = stale;
+        }
+    }
+
+    pub fn process<R: Read>(&mut self, mut reader: R) -> io::Result<()> {
+        let mut buffer = [0; 8192];
+        loop {
+            let size = reader.read(&mut buffer)?;
+            if size == 0 {
+                break;
+            }
+            self.process_buffer(&buffer[..size])?;
+        }
+        Ok(())
+    }
+
+    // Feeds a chunk of the length-prefixed stream. Messages split across chunk
+    // boundaries are accumulated in the cache until they are complete.
+    pub fn process_buffer(&mut self, data: &[u8]) -> io::Result<()> {
+        let mut index = 0;
+        while index < data.len() {
+            if self.size == 0 {
+                let remaining = data.len() - index;
+                if (self.cache.is_empty() && remaining < 3) || self.cache.len() == 1 {
+                    self.cache.push(data[index]);
+                    index += 1;
+                    continue;
+                }
+
+                let message_size = if self.cache.is_empty() {
+                    // Read the message size directly from the input buffer
+                    let message_size = read_big_endian(&data[index..]);
+                    index += 2;
+                    message_size
+                } else {
+                    // Read the message size from the cache
+                    let message_size = read_big_endian(&self.cache);
+                    self.cache.clear();
+                    message_size
+                };
+                self.size = message_size as usize;
+
+                // Zero sized messages carry nothing to process
+                if self.size == 0 {
+                    continue;
+                }
+            }
+
+            let remaining = data.len() - index;
+            if !self.cache.is_empty() {
+                let tail = std::cmp::min(self.size - self.cache.len(), remaining);
+                self.cache.extend_from_slice(&data[index..index + tail]);
+                index += tail;
+                if self.cache.len() < self.size {
+                    continue;
+                }
+            } else if self.size > remaining {
+                self.cache.reserve(self.size);
+                self.cache.extend_from_slice(&data[index..]);
+                index = data.len();
+                continue;
+            }
+
+            let result = if self.cache.is_empty() {
+                // Process the current message directly from the input buffer
+                let result = self.replay_message(&data[index..index + self.size]);
+                index += self.size;
+                result
+            } else {
+                // Process the current message from the cache, keeping its capacity for reuse
+                let mut message = std::mem::take(&mut self.cache);
+                let result = self.replay_message(&message);
+                message.clear();
+                self.cache = message;
+                result
+            };
+            self.size = 0;
+
+            result.map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+        }
+        Ok(())
+    }
+
+    pub fn process_message(&mut self, buffer: &[u8]) -> Result<(), ErrorCode> {
+        let message = ITCHMessage::decode(buffer)?;
+        self.on_message(&message)
+    }
+
+    // Processes a message of a replayed stream. Messages that cannot be applied are
+    // reported to the market handler and skipped, only a message size that does not
+    // match its type stops the replay as the stream can no longer be trusted.
+    pub fn replay_message(&mut self, buffer: &[u8]) -> Result<(), ErrorCode> {
+        match self.process_message(buffer) {
+            Err(error @ ErrorCode::MessageInvalid(ITCHError::InvalidMessageSize { .. } | ITCHError::TruncatedMessage { .. })) => Err(error),
+            Err(error) => {
+                // Call the corresponding MarketHandler
+                self.manager.handler.on_message_error(buffer, &error);
+                Ok(())
+            },
+            Ok(()) => Ok(()),
+        }
+    }
+
+    // Translates order level messages into market manager operations. The feed has
+    // already been matched by the exchange, so the manager should keep matching disabled.
+    pub fn on_message(&mut self, message: &ITCHMessage<'_>) -> Result<(), ErrorCode> {
+        self.manager.set_timestamp(message.timestamp())?;
+        match message {
+            ITCHMessage::SystemEvent(message) => {
+                self.system_event = Some(SystemEvent::try_from(message.event_code())?);
+                Ok(())
+            },
+            ITCHMessage::StockDirectory(message) => {
+                let symbol = Symbol::from(message);
+                self.manager.add_symbol(symbol.clone())?;
+                if let Err(error) = self.manager.add_order_book(&symbol) {
+                    self.manager.delete_symbol(symbol.id)?;
+                    return Err(error);
+                }
+                // Books created during a gap may already be missing updates
+                self.manager.order_books.get_order_book(&symbol.id)?.stale = self.stale;
+                Ok(())
+            },
+            ITCHMessage::StockTradingAction(message) => {
+                let status = TradingStatus::try_from(message.trading_state())?;
+                self.update_trading_state(message.stock_locate(), |state| state.status = status)
+            },
+            ITCHMessage::RegSHO(message) => {
+                let restricted = message.reg_sho_action() != b'0';
+                self.update_trading_state(message.stock_locate(), |state| state.reg_sho_restricted = restricted)
+            },
+            ITCHMessage::OperationalHalt(message) => {
+                // Halts on BX ('B') and PSX ('X') do not stop trading on Nasdaq ('Q')
+                if message.market_code() != b'Q' {
+                    return Ok(());
+                }
+                let halted = message.operational_halt_action() == b'H';
+                self.update_trading_state(message.stock_locate(), |state| state.operational_halt = halted)
+            },
+            ITCHMessage::MarketParticipantPosition(message) => {
+                let mpid = *message.mpid();
+                self.update_reference(message.stock_locate(), ReferenceUpdate::ParticipantPosition(mpid), |order_book| {
+                    order_book.reference.participants.insert(mpid, ParticipantPosition::from(message));
+                })
+            },
+            ITCHMessage::MWCBDecline(message) => {
+                self.manager.order_books.circuit_breaker_mut().update_decline_levels(message);
+
+                // Call the corresponding MarketHandler
+                self.manager.handler.on_circuit_breaker(self.manager.order_books.circuit_breaker());
+                Ok(())
+            },
+            ITCHMessage::MWCBStatus(message) => {
+                self.manager.order_books.circuit_breaker_mut().update_breached_level(message.breached_level());
+
+                // Call the corresponding MarketHandler
+                self.manager.handler.on_circuit_breaker(self.manager.order_books.circuit_breaker());
+                Ok(())
+            },
+            ITCHMessage::IPOQuotingPeriodUpdate(message) => {
+                self.update_reference(message.stock_locate(), ReferenceUpdate::IpoQuotingPeriod, |order_book| {
+                    order_book.reference.ipo_quoting_period = Some(IpoQuotingPeriod::from(message));
+                })
+            },
+            ITCHMessage::LULDAuctionCollar(message) => {
+                self.update_reference(message.stock_locate(), ReferenceUpdate::AuctionCollar, |order_book| {
+                    order_book.reference.auction_collar = Some(AuctionCollar::from(message));
+                })
+            },
+            ITCHMessage::DLCRPriceDiscovery(message) => {
+                self.update_reference(message.stock_locate(), ReferenceUpdate::PriceDiscovery, |order_book| {
+                    order_book.reference.price_discovery = Some(PriceDiscovery::from(message));
+                })
+            },
+            // The feed carries no time in force. Orders leaving the book at the close are
+            // deleted by the exchange itself, so replayed orders are never expired locally.
+            ITCHMessage::AddOrder(message) => {
+                self.manager.add_order(Order::limit(
+                    message.order_reference_number(),
+                    message.stock_locate() as u64,
+                    order_side(message.buy_sell_indicator())?,
+                    message.price() as u64,
+                    message.shares() as u64,
+                ))
+            },
+            ITCHMessage::AddOrderMPID(message) => {
+                self.manager.add_order(Order {
+                    mpid: Some(*message.attribution()),
+                    ..Order::limit(
+                        message.order_reference_number(),
+                        message.stock_locate() as u64,
+                        order_side(message.buy_sell_indicator())?,
+                        message.price() as u64,
+                        message.shares() as u64,
+                    )
+                })
+            },
+            ITCHMessage::OrderExecuted(message) => {
+                // Executions without a price happen at the resting order price
+                let order = self.manager.orders.get_order(message.order_reference_number())?;
+                let quantity = std::cmp::min(message.executed_shares() as u64, order.leaves_quantity);
+                let trade = Trade::execution(order, message.match_number(), order.price, quantity, true, message.timestamp());
+                self.print(trade)?;
+                self.manager.execute_order(message.order_reference_number(), message.executed_shares() as u64)
+            },
+            ITCHMessage::OrderExecutedWithPrice(message) => {
+                let order = self.manager.orders.get_order(message.order_reference_number())?;
+                let quantity = std::cmp::min(message.executed_shares() as u64, order.leaves_quantity);
+                let printable = message.printable() == b'Y';
+                let trade = Trade::execution(order, message.match_number(), message.execution_price() as u64, quantity, printable, message.timestamp());
+                self.print(trade)?;
+                self.manager.execute_order_with_price(message.order_reference_number(), message.execution_price() as u64, message.executed_shares() as u64)
+            },
+            ITCHMessage::OrderCancel(message) => {
+                self.manager.reduce_order(message.order_reference_number(), message.cancelled_shares() as u64)
+            },
+            ITCHMessage::OrderDelete(message) => {
+                self.manager.delete_order(message.order_reference_number())
+            },
+            ITCHMessage::OrderReplace(message) => {
+                self.manager.replace_order(
+                    message.original_order_reference_number(),
+                    message.new_order_reference_number(),
+                    message.price() as u64,
+                    message.shares() as u64,
+                )
+            },
+            // Executions of non-displayed orders and crosses never touch the visible book
+            ITCHMessage::Trade(message) => {
+                self.print(Trade::from(message))
+            },
+            ITCHMessage::CrossTrade(message) => {
+                self.print(Trade::from(message))
+            },
+            ITCHMessage::BrokenTrade(message) => {
+                // Prints missed before the feed was joined cannot be broken
+                let Some(trade) = self.tape.break_trade(message.match_number()) else {
+                    return Ok(());
+                };
+                let order_book = self.manager.order_books.get_order_book(&trade.symbol_id)?;
+                order_book.statistics.remove_trade(&trade, self.tape.last_price(trade.symbol_id));
+
+                // Stop orders are triggered from the last trade still standing
+                match self.tape.last_trade(trade.symbol_id) {
+                    Some(last) => order_book.update_last_trade_price(last.price),
+                    None => order_book.reset_last_trade_price(),
+                }
+
+                // Call the corresponding MarketHandler
+                self.manager.handler.on_broken_trade(order_book, &trade);
+                Ok(())
+            },
+            ITCHMessage::NOII(message) => {
+                let order_book = self.manager.order_books.get_order_book(&(message.stock_locate() as u64))?;
+                let previous = order_book.imbalance.replace(Imbalance::from(message));
+
+                // Call the corresponding MarketHandler
+                self.manager.handler.on_imbalance(order_book, previous);
+                Ok(())
+            },
+            // Remaining messages do not modify resting orders
+            _ => Ok(()),
+        }
+    }
+
+    // Puts the trade on the tape and into the statistics of its order book
+    fn print(&mut self, trade: Trade) -> Result<(), ErrorCode> {
+        self.manager.order_books.get_order_book(&trade.symbol_id)?.statistics.add_trade(&trade);
+        self.tape.record(trade);
+        Ok(())
+    }
+
+    fn update_reference<F>(&mut self, stock_locate: u16, update: ReferenceUpdate, apply: F) -> Result<(), ErrorCode>
+    where
+        F: FnOnce(&mut OrderBook),
+    {
+        let order_book = self.manager.order_books.get_order_book(&(stock_locate as u64))?;
+        apply(order_book);
+
+        // Call the corresponding MarketHandler
+        self.manager.handler.on_reference_data(order_book, update);
+        Ok(())
+    }
+
+    fn update_trading_state<F>(&mut self, stock_locate: u16, update: F) -> Result<(), ErrorCode>
+    where
+        F: FnOnce(&mut TradingState),
+    {
+        let order_book = self.manager.order_books.get_order_book(&(stock_locate as u64))?;
+
+        let mut trading_state = order_book.trading_state;
+        update(&mut trading_state);
+
+        if let Some(previous) = order_book.update_trading_state(trading_state) {
+            // Call the corresponding MarketHandler
+            self.manager.handler.on_trading_status_change(order_book, previous);
+        }
+        Ok(())
+    }
+}
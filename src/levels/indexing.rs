@@ -1,268 +1,464 @@
+use generational_arena::{Arena, Index};
 
+use crate::orders::order::ErrorCode;
 
-use std::{cell::{RefCell}, rc::{Rc, Weak}};
-use std::fmt::Debug;
+use super::{ladder::PriceLadder, level::{Level, LevelType}};
 
-use crate::orders::order::ErrorCode;
+// Typed handle of a price level in a level container. It stays valid while the level
+// is in its container. Tree handles are never reused for another level, ladder handles
+// are keyed by price so they survive recentering.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct LevelHandle(pub(super) HandleKey);
 
-use super::level::Level;
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub(super) enum HandleKey {
+    Node(Index),
+    Price(u64),
+}
 
-pub trait TreeOps {
-    fn find_node_by_price(&mut self, price: u64) -> Option<Rc<RefCell<LevelNode>>>;
-    fn insert(&mut self, candidate_node: Rc<RefCell<LevelNode>>);
-    fn get_next_lower_level(&self, level_node: Rc<RefCell<LevelNode>>) -> Result<Option<Rc<RefCell<LevelNode>>>, ErrorCode>;
-    fn get_next_higher_level(&self, level_node: Rc<RefCell<LevelNode>>) -> Result<Option<Rc<RefCell<LevelNode>>>, ErrorCode>;
+impl LevelHandle {
+    fn index(self) -> Option<Index> {
+        match self.0 {
+            HandleKey::Node(index) => Some(index),
+            HandleKey::Price(_) => None,
+        }
+    }
 }
 
-impl TreeOps for Rc<RefCell<LevelNode>> {
-
-    fn find_node_by_price(&mut self, price: u64) -> Option<Rc<RefCell<LevelNode>>>                                          
-    {
-        let mut current = Some(*self);
-        while let Some(node) = current {
-            if let Ok(borrowed_node) = node.try_borrow() {
-                let node_price = borrowed_node.level.price;
-                if price < node_price {
-                    current = borrowed_node.left.clone();
-                } else if price > node_price {
-                    current = borrowed_node.right.clone();
-                } else {
-                    return Some(node.clone());
-                }
-            } else {
-                // Optionally handle the error or simply skip/break
-                break;
-            }
+// Price levels of one side of an order book, ordered from the best one
+pub trait LevelIndex {
+    fn level_type(&self) -> LevelType;
+    fn len(&self) -> usize;
+    fn get(&self, handle: LevelHandle) -> Option<&Level>;
+    fn get_mut(&mut self, handle: LevelHandle) -> Option<&mut Level>;
+    fn find(&self, price: u64) -> Option<LevelHandle>;
+    fn insert(&mut self, level: Level) -> Result<LevelHandle, ErrorCode>;
+    fn remove(&mut self, handle: LevelHandle) -> Result<Level, ErrorCode>;
+    // Highest level for bids and lowest level for asks
+    fn best(&self) -> Option<LevelHandle>;
+    // Next level away from the best one
+    fn next(&self, handle: LevelHandle) -> Option<LevelHandle>;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // Levels from the best one
+    fn iter(&self) -> LevelIter<'_, Self> {
+        LevelIter { levels: self, next: self.best() }
+    }
+}
+
+// Level container used for the bids and asks of an order book
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub enum LevelContainer {
+    // Balanced tree, suited to any spread of prices
+    #[default]
+    Tree,
+    // Direct-indexed ladder of the given tick size, suited to prices around the inside.
+    // Capacity is the number of slots of the window, deeper levels are kept in a tree.
+    Ladder { tick_size: u64, capacity: usize },
+}
+
+pub enum PriceLevels {
+    Tree(LevelTree),
+    Ladder(PriceLadder),
+}
+
+impl PriceLevels {
+    pub fn new(level_type: LevelType, container: LevelContainer) -> Self {
+        match container {
+            LevelContainer::Tree => PriceLevels::Tree(LevelTree::new(level_type)),
+            LevelContainer::Ladder { tick_size, capacity } => PriceLevels::Ladder(PriceLadder::new(level_type, tick_size, capacity)),
         }
-        None
     }
-    
-    fn insert(&mut self, candidate_node: Rc<RefCell<LevelNode>>) -> Result<Option<Rc<RefCell<LevelNode>>>, ErrorCode>                        
-    {
-        let candidate_price = candidate_node.try_borrow().map(|n| n.level.price).expect("Failed to borrow candidate node");
-        let mut current = Some(self);
-        loop {
-            match current {
-                Some(node) => {
-                    let mut borrowed_node = node.try_borrow_mut().expect("Failed to borrow current node");
-                    if candidate_price < borrowed_node.level.price {
-                        if let Some(left) = &mut borrowed_node.left {
-                            current = Some(left);
-                        } else {
-                            borrowed_node.left = Some(candidate_node.clone()); // Insert here
-                            // Assuming `set_parent` properly sets the parent of the node.
-                            candidate_node.set_parent(node);
-                            break;
-                        }
-                    } else {
-                        if let Some(right) = &mut borrowed_node.right {
-                            current = Some(right);
-                        } else {
-                            borrowed_node.right = Some(candidate_node.clone()); // Insert here
-                            // Assuming `set_parent` properly sets the parent of the node.
-                            candidate_node.set_parent(node);
-                            break;
-                        }
-                    }
-                },
-                None => break,
-            }
+
+    // Checks that a level of the given price can be kept in the container
+    pub fn validate_price(&self, price: u64) -> Result<(), ErrorCode> {
+        match self {
+            PriceLevels::Tree(_) => Ok(()),
+            PriceLevels::Ladder(ladder) if !price.is_multiple_of(ladder.tick_size()) => Err(ErrorCode::LevelPriceInvalid),
+            PriceLevels::Ladder(_) => Ok(()),
         }
     }
+}
 
-    fn get_next_lower_level(&self, level_node: Rc<RefCell<LevelNode>>) -> Result<Option<Rc<RefCell<LevelNode>>>, ErrorCode> {
-        // verify rc clone borrow here
-        let mut current: Option<&Rc<RefCell<LevelNode>>> = Some(&level_node);
-        let mut last_smaller: Option<Rc<RefCell<LevelNode>>> = None;
-        
-        loop {
-            match current {
-                Some(node) => {
-                    let borrowed_node = node.try_borrow().expect("Failed to borrow node");
-                    
-                    if let Some(left) = &borrowed_node.left {
-                        // Go left once
-                        let mut deepest_right = left;
-                        // Then go right as far as possible
-                        while let Some(right) = deepest_right.try_borrow().expect("should borrow right").right.as_ref() {
-                            deepest_right = right;
-                        }
-                        return Some(deepest_right.clone());
-                    } else {
-                        // No left child, traverse up to find a smaller node
-                        current = borrowed_node.parent.as_ref().map(|p| p.upgrade().expect("Failed to upgrade weak reference"));
-                        if current.is_some() && current.unwrap().try_borrow().expect("Failed to borrow parent").level.price < borrowed_node.level.price {
-                            last_smaller = current.cloned();
-                        } else {
-                            break;
-                        }
-                    }
-                },
-                None => break,
-            }
+impl LevelIndex for PriceLevels {
+    fn level_type(&self) -> LevelType {
+        match self {
+            PriceLevels::Tree(tree) => tree.level_type(),
+            PriceLevels::Ladder(ladder) => ladder.level_type(),
         }
-        
-        last_smaller
-    }
-
-    fn get_next_higher_level(&self, level_node: Rc<RefCell<LevelNode>>) -> Result<Option<Rc<RefCell<LevelNode>>>, ErrorCode> {
-        let mut current: Option<&Rc<RefCell<LevelNode>>> = Some(&level_node);
-        let mut last_greater: Option<Rc<RefCell<LevelNode>>> = None;
-        
-        loop {
-            match current {
-                Some(node) => {
-                    let borrowed_node = node.try_borrow().expect("Failed to borrow node");
-                    
-                    if let Some(right) = &borrowed_node.right {
-                        // Go right once
-                        let mut deepest_left = right;
-                        // Then go left as far as possible
-                        while let Some(left) = deepest_left.try_borrow().expect("should borrow left").left.as_ref() {
-                            deepest_left = left;
-                        }
-                        return Ok(Some(deepest_left.clone()));
-                    } else {
-                        // No right child, traverse up to find a greater node
-                        current = borrowed_node.parent.as_ref().map(|p| p.upgrade().as_ref().expect("Failed to upgrade weak reference"));
-                        if current.is_some() && current.unwrap().try_borrow().expect("Failed to borrow parent").level.price > borrowed_node.level.price {
-                            last_greater = current.cloned();
-                        } else {
-                            break;
-                        }
-                    }
-                },
-                None => break,
-            }
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            PriceLevels::Tree(tree) => tree.len(),
+            PriceLevels::Ladder(ladder) => ladder.len(),
+        }
+    }
+
+    fn get(&self, handle: LevelHandle) -> Option<&Level> {
+        match self {
+            PriceLevels::Tree(tree) => tree.get(handle),
+            PriceLevels::Ladder(ladder) => ladder.get(handle),
+        }
+    }
+
+    fn get_mut(&mut self, handle: LevelHandle) -> Option<&mut Level> {
+        match self {
+            PriceLevels::Tree(tree) => tree.get_mut(handle),
+            PriceLevels::Ladder(ladder) => ladder.get_mut(handle),
+        }
+    }
+
+    fn find(&self, price: u64) -> Option<LevelHandle> {
+        match self {
+            PriceLevels::Tree(tree) => tree.find(price),
+            PriceLevels::Ladder(ladder) => ladder.find(price),
         }
-        
-        last_greater
     }
+
+    fn insert(&mut self, level: Level) -> Result<LevelHandle, ErrorCode> {
+        match self {
+            PriceLevels::Tree(tree) => tree.insert(level),
+            PriceLevels::Ladder(ladder) => ladder.insert(level),
+        }
+    }
+
+    fn remove(&mut self, handle: LevelHandle) -> Result<Level, ErrorCode> {
+        match self {
+            PriceLevels::Tree(tree) => tree.remove(handle),
+            PriceLevels::Ladder(ladder) => ladder.remove(handle),
+        }
+    }
+
+    fn best(&self) -> Option<LevelHandle> {
+        match self {
+            PriceLevels::Tree(tree) => tree.best(),
+            PriceLevels::Ladder(ladder) => ladder.best(),
+        }
+    }
+
+    fn next(&self, handle: LevelHandle) -> Option<LevelHandle> {
+        match self {
+            PriceLevels::Tree(tree) => tree.next(handle),
+            PriceLevels::Ladder(ladder) => ladder.next(handle),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct LevelNode {
+    pub level: Level,
+    parent: Option<LevelHandle>,
+    left: Option<LevelHandle>,
+    right: Option<LevelHandle>,
+    height: i32,
 }
 
-pub trait TreeRemoval {
-    fn remove(&mut self, price: u64) -> Result<Option<Rc<RefCell<LevelNode>>>, ErrorCode>;
-    fn remove_recursive(&mut self, price: u64) -> Result<Option<Rc<RefCell<LevelNode>>>, ErrorCode>;
-    fn find_min(&self) -> Result<Option<Rc<RefCell<LevelNode>>>, ErrorCode>;
+// AVL tree of price levels keyed by price. Nodes live in a generational arena and are
+// linked by handles, so every operation is O(log n) in the worst case and nothing is
+// borrowed at runtime. Removing a level relinks the tree without moving other levels,
+// so handles of the remaining levels stay valid.
+//
+// The best level is the highest one in bid trees and the lowest one in ask trees.
+#[derive(Debug)]
+pub struct LevelTree {
+    level_type: LevelType,
+    nodes: Arena<LevelNode>,
+    root: Option<LevelHandle>,
 }
 
-impl TreeRemoval for Rc<RefCell<LevelNode>>  {
-    
-    fn remove(&mut self, price: u64) -> Result<Option<Rc<RefCell<LevelNode>>>, ErrorCode>
-    {
-        self.remove_recursive(price)
+impl LevelTree {
+    pub fn new(level_type: LevelType) -> Self {
+        LevelTree {
+            level_type,
+            nodes: Arena::new(),
+            root: None,
+        }
     }
 
-    fn remove_recursive(&mut self, price: u64) -> Result<Option<Rc<RefCell<LevelNode>>>, ErrorCode> {
-        let mut node_borrowed = self.try_borrow_mut().map_err(|_| ErrorCode::OtherError("Failed to borrow node for modification".to_string()))?;
-        if price < node_borrowed.level.price {
-            if let Some(left) = &node_borrowed.left {
-                node_borrowed.left = left.remove_recursive(price);
-            } else {
-                return Err(ErrorCode::OrderNotFound);
-            }
-        } else if price > node_borrowed.level.price {
-            if let Some(right) = &node_borrowed.right {
-                node_borrowed.right = right.remove_recursive(price);
-            } else {
-                return Err(ErrorCode::OrderNotFound);
+    pub fn lowest(&self) -> Option<LevelHandle> {
+        self.root.map(|root| self.min_from(root))
+    }
+
+    pub fn highest(&self) -> Option<LevelHandle> {
+        self.root.map(|root| self.max_from(root))
+    }
+
+    // Number of levels on the longest path from the root, at most ~1.44 log2(n) in an AVL tree
+    pub fn depth(&self) -> usize {
+        self.height(self.root) as usize
+    }
+
+    pub fn next_lower(&self, handle: LevelHandle) -> Option<LevelHandle> {
+        let node = self.nodes.get(handle.index()?)?;
+        if let Some(left) = node.left {
+            return Some(self.max_from(left));
+        }
+
+        // Climb until we come from a right subtree
+        let mut current = handle;
+        while let Some(parent) = self.node(current).parent {
+            if self.node(parent).right == Some(current) {
+                return Some(parent);
             }
-        } else {
-            if node_borrowed.left.is_none() {
-                return Ok(node_borrowed.right.take());
-            } else if node_borrowed.right.is_none() {
-                return Ok(node_borrowed.left.take());
+            current = parent;
+        }
+        None
+    }
+
+    pub fn next_higher(&self, handle: LevelHandle) -> Option<LevelHandle> {
+        let node = self.nodes.get(handle.index()?)?;
+        if let Some(right) = node.right {
+            return Some(self.min_from(right));
+        }
+
+        // Climb until we come from a left subtree
+        let mut current = handle;
+        while let Some(parent) = self.node(current).parent {
+            if self.node(parent).left == Some(current) {
+                return Some(parent);
             }
+            current = parent;
+        }
+        None
+    }
+
+    fn node(&self, handle: LevelHandle) -> &LevelNode {
+        &self.nodes[handle.index().expect("tree links hold node handles")]
+    }
+
+    fn node_mut(&mut self, handle: LevelHandle) -> &mut LevelNode {
+        &mut self.nodes[handle.index().expect("tree links hold node handles")]
+    }
+
+    fn min_from(&self, mut handle: LevelHandle) -> LevelHandle {
+        while let Some(left) = self.node(handle).left {
+            handle = left;
+        }
+        handle
+    }
+
+    fn max_from(&self, mut handle: LevelHandle) -> LevelHandle {
+        while let Some(right) = self.node(handle).right {
+            handle = right;
+        }
+        handle
+    }
+
+    fn height(&self, handle: Option<LevelHandle>) -> i32 {
+        handle.map_or(0, |handle| self.node(handle).height)
+    }
+
+    fn balance_factor(&self, handle: LevelHandle) -> i32 {
+        let node = self.node(handle);
+        self.height(node.left) - self.height(node.right)
+    }
+
+    fn update_height(&mut self, handle: LevelHandle) {
+        let node = self.node(handle);
+        let height = 1 + std::cmp::max(self.height(node.left), self.height(node.right));
+        self.node_mut(handle).height = height;
+    }
 
-            let successor = node_borrowed.right.as_ref().ok_or(ErrorCode::OrderNotFound)?.find_min();
-            let successor_price = successor.try_borrow().map_err(|_| ErrorCode::OtherError("Failed to borrow successor for price retrieval".to_string()))?.level.price;
-            node_borrowed.level.price = successor_price;
-            node_borrowed.right = node_borrowed.right.as_ref().ok_or(ErrorCode::OrderNotFound)?.remove_recursive(successor_price);
+    // Points the parent, or the root, to the new child instead of the old one
+    fn replace_child(&mut self, parent: Option<LevelHandle>, old: LevelHandle, new: Option<LevelHandle>) {
+        if let Some(new) = new {
+            self.node_mut(new).parent = parent;
         }
-        Ok(None)
-    }
-    
-    // Helper function to find the minimum node starting from a given node
-    fn find_min(&self) -> Result<Option<Rc<RefCell<LevelNode>>>, ErrorCode> {
-        let mut current = self.clone();
-        loop {
-            let borrowed_current = current.try_borrow().map_err(|_| ErrorCode::OtherError("Failed to borrow node for minimum search".to_string()))?;
-            match &borrowed_current.left {
-                Some(left) => current = left.clone(),
-                None => break,
+        match parent {
+            Some(parent) if self.node(parent).left == Some(old) => self.node_mut(parent).left = new,
+            Some(parent) => self.node_mut(parent).right = new,
+            None => self.root = new,
+        }
+    }
+
+    fn rotate_left(&mut self, handle: LevelHandle) -> LevelHandle {
+        let pivot = self.node(handle).right.expect("left rotation needs a right child");
+        let parent = self.node(handle).parent;
+        let inner = self.node(pivot).left;
+
+        self.node_mut(handle).right = inner;
+        if let Some(inner) = inner {
+            self.node_mut(inner).parent = Some(handle);
+        }
+        self.replace_child(parent, handle, Some(pivot));
+        self.node_mut(pivot).left = Some(handle);
+        self.node_mut(handle).parent = Some(pivot);
+
+        self.update_height(handle);
+        self.update_height(pivot);
+        pivot
+    }
+
+    fn rotate_right(&mut self, handle: LevelHandle) -> LevelHandle {
+        let pivot = self.node(handle).left.expect("right rotation needs a left child");
+        let parent = self.node(handle).parent;
+        let inner = self.node(pivot).right;
+
+        self.node_mut(handle).left = inner;
+        if let Some(inner) = inner {
+            self.node_mut(inner).parent = Some(handle);
+        }
+        self.replace_child(parent, handle, Some(pivot));
+        self.node_mut(pivot).right = Some(handle);
+        self.node_mut(handle).parent = Some(pivot);
+
+        self.update_height(handle);
+        self.update_height(pivot);
+        pivot
+    }
+
+    // Restores heights and balance from the given node up to the root
+    fn rebalance(&mut self, mut current: Option<LevelHandle>) {
+        while let Some(mut handle) = current {
+            self.update_height(handle);
+
+            let balance = self.balance_factor(handle);
+            if balance > 1 {
+                let left = self.node(handle).left.expect("left heavy node has a left child");
+                if self.balance_factor(left) < 0 {
+                    self.rotate_left(left);
+                }
+                handle = self.rotate_right(handle);
+            } else if balance < -1 {
+                let right = self.node(handle).right.expect("right heavy node has a right child");
+                if self.balance_factor(right) > 0 {
+                    self.rotate_right(right);
+                }
+                handle = self.rotate_left(handle);
             }
+
+            current = self.node(handle).parent;
         }
-        Ok(current)
     }
 }
 
-#[derive(Debug)]
-pub struct LevelNode
-{
-    pub level: Level,
-    pub parent: Option<Weak<RefCell<LevelNode>>>,
-    pub left: Option<Rc<RefCell<LevelNode>>>,
-    pub right: Option<Rc<RefCell<LevelNode>>>,
-}
+impl LevelIndex for LevelTree {
+    fn level_type(&self) -> LevelType {
+        self.level_type
+    }
+
+    fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    fn get(&self, handle: LevelHandle) -> Option<&Level> {
+        handle.index().and_then(|index| self.nodes.get(index)).map(|node| &node.level)
+    }
+
+    fn get_mut(&mut self, handle: LevelHandle) -> Option<&mut Level> {
+        handle.index().and_then(|index| self.nodes.get_mut(index)).map(|node| &mut node.level)
+    }
+
+    fn find(&self, price: u64) -> Option<LevelHandle> {
+        let mut current = self.root;
+        while let Some(handle) = current {
+            let node = self.node(handle);
+            current = match price.cmp(&node.level.price) {
+                std::cmp::Ordering::Less => node.left,
+                std::cmp::Ordering::Greater => node.right,
+                std::cmp::Ordering::Equal => return Some(handle),
+            };
+        }
+        None
+    }
+
+    fn insert(&mut self, level: Level) -> Result<LevelHandle, ErrorCode> {
+        // Find the parent of the new level
+        let mut parent = None;
+        let mut current = self.root;
+        while let Some(handle) = current {
+            let node = self.node(handle);
+            parent = Some(handle);
+            current = match level.price.cmp(&node.level.price) {
+                std::cmp::Ordering::Less => node.left,
+                std::cmp::Ordering::Greater => node.right,
+                std::cmp::Ordering::Equal => return Err(ErrorCode::LevelDuplicate),
+            };
+        }
+
+        let price = level.price;
+        let handle = LevelHandle(HandleKey::Node(self.nodes.insert(LevelNode { level, parent, left: None, right: None, height: 1 })));
 
-impl LevelNode {
-    // Function that returns the level of the node.
-    // For demonstration, we use Result to encapsulate the level or an error.
-    pub fn level(&self) -> Result<&Level, ErrorCode> {
-        Ok(&self.level)
+        // Link the new level as a leaf and restore the balance up to the root
+        match parent {
+            Some(parent) if price < self.node(parent).level.price => self.node_mut(parent).left = Some(handle),
+            Some(parent) => self.node_mut(parent).right = Some(handle),
+            None => self.root = Some(handle),
+        }
+        self.rebalance(parent);
+
+        Ok(handle)
     }
 
-    pub fn level_mut(&mut self) -> Result<&mut Level, ErrorCode> {
-        Ok(&mut self.level)
+    fn remove(&mut self, handle: LevelHandle) -> Result<Level, ErrorCode> {
+        let (parent, left, right, height) = {
+            let node = handle.index().and_then(|index| self.nodes.get(index)).ok_or(ErrorCode::LevelNotFound)?;
+            (node.parent, node.left, node.right, node.height)
+        };
+
+        let rebalance_from = match (left, right) {
+            (Some(left), Some(right)) => {
+                // Put the successor in place of the removed level
+                let successor = self.min_from(right);
+                let rebalance_from = if successor == right {
+                    successor
+                } else {
+                    let successor_parent = self.node(successor).parent;
+                    let successor_right = self.node(successor).right;
+                    self.replace_child(successor_parent, successor, successor_right);
+                    self.node_mut(successor).right = Some(right);
+                    self.node_mut(right).parent = Some(successor);
+                    successor_parent.unwrap_or(successor)
+                };
+                self.node_mut(successor).left = Some(left);
+                self.node_mut(left).parent = Some(successor);
+                self.node_mut(successor).height = height;
+                self.replace_child(parent, handle, Some(successor));
+                Some(rebalance_from)
+            },
+            (child, None) | (None, child) => {
+                self.replace_child(parent, handle, child);
+                parent
+            },
+        };
+
+        let node = handle.index().and_then(|index| self.nodes.remove(index)).ok_or(ErrorCode::LevelNotFound)?;
+        self.rebalance(rebalance_from);
+
+        Ok(node.level)
+    }
+
+    fn best(&self) -> Option<LevelHandle> {
+        match self.level_type {
+            LevelType::Bid => self.highest(),
+            LevelType::Ask => self.lowest(),
+        }
     }
+
+    fn next(&self, handle: LevelHandle) -> Option<LevelHandle> {
+        match self.level_type {
+            LevelType::Bid => self.next_lower(handle),
+            LevelType::Ask => self.next_higher(handle),
+        }
+    }
+
 }
 
+pub struct LevelIter<'a, L: LevelIndex + ?Sized> {
+    levels: &'a L,
+    next: Option<LevelHandle>,
+}
+
+impl<'a, L: LevelIndex + ?Sized> Iterator for LevelIter<'a, L> {
+    type Item = &'a Level;
 
-// struct InOrderIterator 
-// where
-//     
-// {
-//     stack: Vec,
-//     next_node: Option<Rc<RefCell<LevelNode>>>,
-//     _marker: PhantomData<(&T, PhantomData)>,
-// }
-
-// // impl<'a> LevelNode {
-// //     fn in_order_iterator(&self) -> InOrderIterator<'a> {
-// //         let mut iterator = InOrderIterator {
-// //             stack: Veself.new(),
-// //             next_node: self,
-// //         };
-// //         iterator.move_to_leftmost();
-// //         iterator
-// //     }
-// // }
-
-// impl InOrderIterator
-// where
-//     
-// {
-//     fn move_to_leftmost(&mut self) {
-//         while let Some(node) = self.next_node.try_borrow() {
-//             self.stack.push(node.try_borrow());
-//             self.next_node = node.try_borrow().left;
-//         }
-//     }
-// }
-
-// impl Iterator for InOrderIterator
-// where
-//       // Assuming T is a type that implements TreeOps. This might be redundant based on your actual trait definitions.
-// {
-//     type Item = T; // Assuming A is defined elsewhere
-
-//     fn next(&mut self) -> Option<Self::Item> {
-//         if let Some(node) = self.stack.pop() {
-//             self.next_node = node.try_borrow().right;
-//             self.move_to_leftmost();
-//             Some(node) // This line may need adjustment based on the actual return type you intended for `Some(node)`
-//         } else {
-//             None
-//         }
-//     }
-// }
+    fn next(&mut self) -> Option<Self::Item> {
+        let handle = self.next?;
+        self.next = self.levels.next(handle);
+        self.levels.get(handle)
+    }
+}
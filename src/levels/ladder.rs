@@ -0,0 +1,286 @@
+use crate::orders::order::ErrorCode;
+
+use super::{indexing::{HandleKey, LevelHandle, LevelIndex, LevelTree}, level::{Level, LevelType}};
+
+// Slots tracked by one word of the occupancy bitmap
+const WORD_BITS: usize = u64::BITS as usize;
+
+// Direct-indexed price levels around the inside of the book. Slot i holds the level
+// at base + i * tick_size and a bitmap marks the slots in use, so levels are found,
+// added and removed in O(1) and the best level is cached. The window has a fixed
+// capacity and always holds the best level: a better level outside of it recenters
+// the window, deeper levels outside of it are kept in an overflow tree.
+//
+// Prices must be multiples of the tick size. Handles are keyed by price, so they
+// survive recentering.
+#[derive(Debug)]
+pub struct PriceLadder {
+    level_type: LevelType,
+    tick_size: u64,
+    base: u64,
+    slots: Vec<Option<Level>>,
+    occupied: Vec<u64>,
+    // Levels in the window
+    len: usize,
+    best: Option<usize>,
+    // Levels beyond the deep end of the window, all of them worse than the window ones
+    overflow: LevelTree,
+}
+
+impl PriceLadder {
+    pub fn new(level_type: LevelType, tick_size: u64, capacity: usize) -> Self {
+        // Whole bitmap words keep the slot scans simple
+        let capacity = std::cmp::max(capacity, 1).div_ceil(WORD_BITS) * WORD_BITS;
+        PriceLadder {
+            level_type,
+            tick_size: std::cmp::max(tick_size, 1),
+            base: 0,
+            slots: (0..capacity).map(|_| None).collect(),
+            occupied: vec![0; capacity / WORD_BITS],
+            len: 0,
+            best: None,
+            overflow: LevelTree::new(level_type),
+        }
+    }
+
+    pub fn tick_size(&self) -> u64 {
+        self.tick_size
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.slots.len()
+    }
+
+    // Number of levels kept outside of the window
+    pub fn overflow_len(&self) -> usize {
+        self.overflow.len()
+    }
+
+    // Lowest price covered by the window
+    pub fn base(&self) -> u64 {
+        self.base
+    }
+
+    fn slot(&self, price: u64) -> Option<usize> {
+        if price < self.base || !(price - self.base).is_multiple_of(self.tick_size) {
+            return None;
+        }
+        let slot = (price - self.base) / self.tick_size;
+        usize::try_from(slot).ok().filter(|slot| *slot < self.slots.len())
+    }
+
+    fn handle(&self, slot: usize) -> LevelHandle {
+        LevelHandle(HandleKey::Price(self.base + slot as u64 * self.tick_size))
+    }
+
+    fn is_occupied(&self, slot: usize) -> bool {
+        self.occupied[slot / WORD_BITS] & (1 << (slot % WORD_BITS)) != 0
+    }
+
+    // Closest slot in use below the given one
+    fn lower(&self, slot: usize) -> Option<usize> {
+        let mut word = slot / WORD_BITS;
+        let mut bits = self.occupied[word] & ((1 << (slot % WORD_BITS)) - 1);
+        loop {
+            if bits != 0 {
+                return Some(word * WORD_BITS + (WORD_BITS - 1 - bits.leading_zeros() as usize));
+            }
+            if word == 0 {
+                return None;
+            }
+            word -= 1;
+            bits = self.occupied[word];
+        }
+    }
+
+    // Closest slot in use above the given one
+    fn higher(&self, slot: usize) -> Option<usize> {
+        let mut word = slot / WORD_BITS;
+        let mut bits = self.occupied[word] & (u64::MAX << (slot % WORD_BITS)) & !(1 << (slot % WORD_BITS));
+        loop {
+            if bits != 0 {
+                return Some(word * WORD_BITS + bits.trailing_zeros() as usize);
+            }
+            word += 1;
+            if word == self.occupied.len() {
+                return None;
+            }
+            bits = self.occupied[word];
+        }
+    }
+
+    // Next slot in use away from the best one
+    fn next_slot(&self, slot: usize) -> Option<usize> {
+        match self.level_type {
+            LevelType::Bid => self.lower(slot),
+            LevelType::Ask => self.higher(slot),
+        }
+    }
+
+    fn place(&mut self, slot: usize, level: Level) {
+        self.slots[slot] = Some(level);
+        self.occupied[slot / WORD_BITS] |= 1 << (slot % WORD_BITS);
+        self.len += 1;
+
+        let improves = match (self.level_type, self.best) {
+            (_, None) => true,
+            (LevelType::Bid, Some(best)) => slot > best,
+            (LevelType::Ask, Some(best)) => slot < best,
+        };
+        if improves {
+            self.best = Some(slot);
+        }
+    }
+
+    fn is_better(&self, price: u64, than: u64) -> bool {
+        match self.level_type {
+            LevelType::Bid => price > than,
+            LevelType::Ask => price < than,
+        }
+    }
+
+    fn price_handle(price: u64) -> LevelHandle {
+        LevelHandle(HandleKey::Price(price))
+    }
+
+    fn handle_price(handle: LevelHandle) -> Option<u64> {
+        match handle.0 {
+            HandleKey::Price(price) => Some(price),
+            HandleKey::Node(_) => None,
+        }
+    }
+
+    // Moves the window so the new best price is in its middle. Window levels that fall
+    // out of it go to the overflow tree and the best overflow levels that fall into it
+    // are taken back.
+    fn recenter(&mut self, price: u64) {
+        let levels: Vec<Level> = self.slots.iter_mut().filter_map(Option::take).collect();
+        self.occupied.iter_mut().for_each(|word| *word = 0);
+        self.len = 0;
+        self.best = None;
+
+        // Leave the same room on both sides, without going below zero
+        let margin = (self.slots.len() / 2) as u64;
+        self.base = price - std::cmp::min(margin, price / self.tick_size) * self.tick_size;
+
+        for level in levels {
+            match self.slot(level.price) {
+                Some(slot) => self.place(slot, level),
+                // Never a duplicate, the price was in the window
+                None => {
+                    let _ = self.overflow.insert(level);
+                },
+            }
+        }
+
+        while let Some(handle) = self.overflow.best() {
+            let Some(slot) = self.overflow.get(handle).and_then(|level| self.slot(level.price)) else {
+                break;
+            };
+            if let Ok(level) = self.overflow.remove(handle) {
+                self.place(slot, level);
+            }
+        }
+    }
+}
+
+impl LevelIndex for PriceLadder {
+    fn level_type(&self) -> LevelType {
+        self.level_type
+    }
+
+    fn len(&self) -> usize {
+        self.len + self.overflow.len()
+    }
+
+    fn get(&self, handle: LevelHandle) -> Option<&Level> {
+        let price = Self::handle_price(handle)?;
+        match self.slot(price) {
+            Some(slot) => self.slots[slot].as_ref(),
+            None => self.overflow.find(price).and_then(|handle| self.overflow.get(handle)),
+        }
+    }
+
+    fn get_mut(&mut self, handle: LevelHandle) -> Option<&mut Level> {
+        let price = Self::handle_price(handle)?;
+        match self.slot(price) {
+            Some(slot) => self.slots[slot].as_mut(),
+            None => self.overflow.find(price).and_then(|handle| self.overflow.get_mut(handle)),
+        }
+    }
+
+    fn find(&self, price: u64) -> Option<LevelHandle> {
+        match self.slot(price) {
+            Some(slot) => Some(slot).filter(|slot| self.is_occupied(*slot)).map(|slot| self.handle(slot)),
+            None => self.overflow.find(price).map(|_| Self::price_handle(price)),
+        }
+    }
+
+    fn insert(&mut self, level: Level) -> Result<LevelHandle, ErrorCode> {
+        if !level.price.is_multiple_of(self.tick_size) {
+            return Err(ErrorCode::LevelPriceInvalid);
+        }
+
+        let slot = match self.slot(level.price) {
+            Some(slot) => slot,
+            // Deeper levels outside of the window do not move it
+            None if self.best.is_some_and(|best| !self.is_better(level.price, self.base + best as u64 * self.tick_size)) => {
+                let price = level.price;
+                self.overflow.insert(level)?;
+                return Ok(Self::price_handle(price));
+            },
+            None => {
+                self.recenter(level.price);
+                self.slot(level.price).ok_or(ErrorCode::LevelPriceInvalid)?
+            },
+        };
+        if self.is_occupied(slot) {
+            return Err(ErrorCode::LevelDuplicate);
+        }
+
+        self.place(slot, level);
+        Ok(self.handle(slot))
+    }
+
+    fn remove(&mut self, handle: LevelHandle) -> Result<Level, ErrorCode> {
+        let price = Self::handle_price(handle).ok_or(ErrorCode::LevelNotFound)?;
+        let Some(slot) = self.slot(price) else {
+            let handle = self.overflow.find(price).ok_or(ErrorCode::LevelNotFound)?;
+            return self.overflow.remove(handle);
+        };
+        let level = self.slots[slot].take().ok_or(ErrorCode::LevelNotFound)?;
+        self.occupied[slot / WORD_BITS] &= !(1 << (slot % WORD_BITS));
+        self.len -= 1;
+
+        // Move the best level to the next one in use
+        if self.best == Some(slot) {
+            self.best = self.next_slot(slot);
+        }
+
+        // Follow the best level into the overflow tree once the window is empty
+        if self.best.is_none() {
+            if let Some(best) = self.overflow.best().and_then(|handle| self.overflow.get(handle)) {
+                self.recenter(best.price);
+            }
+        }
+
+        Ok(level)
+    }
+
+    fn best(&self) -> Option<LevelHandle> {
+        self.best.map(|slot| self.handle(slot))
+    }
+
+    // Window levels come first, the overflow levels are all worse
+    fn next(&self, handle: LevelHandle) -> Option<LevelHandle> {
+        let price = Self::handle_price(handle)?;
+        let next = match self.slot(price) {
+            Some(slot) => match self.next_slot(slot) {
+                Some(next) => return Some(self.handle(next)),
+                None => self.overflow.best(),
+            },
+            None => self.overflow.find(price).and_then(|handle| self.overflow.next(handle)),
+        };
+        next.and_then(|handle| self.overflow.get(handle)).map(|level| Self::price_handle(level.price))
+    }
+}
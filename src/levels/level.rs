@@ -1,102 +1,48 @@
 
-use std::{collections::LinkedList, cmp::Ordering};
-use actix::prelude::*;
+use std::cmp::Ordering;
 
-use orders::order::Order;
-use crate::{order_book::order_book::OrderBook, orders::{self, order::ErrorCode}};
+use crate::orders::{order::ErrorCode, order_pool::{OrderHandle, OrderPool}};
 
-use super::indexing::LevelNode;
+use super::order_queue::{OrderList, QueueIter};
 
 pub trait LevelOps 
 {
-    fn subtract_volumes(&mut self, order: &Order);
-    fn unlink_order(&mut self, order: &Order);
-    fn link_order(&mut self, order: &Order);
-    fn add_volumes(&mut self, order: &Order) ;
-    fn conditional_unlink_order(&mut self, order: &Order);
-    fn process_level(&mut self, order_book: &mut OrderBook, order: &mut Order);
+    fn unlink_order(&mut self, pool: &mut OrderPool, handle: OrderHandle) -> Result<(), ErrorCode>;
+    fn link_order(&mut self, pool: &mut OrderPool, handle: OrderHandle) -> Result<(), ErrorCode>;
 }
 
 impl LevelOps for Level  
 {
-    fn subtract_volumes(&mut self, order: &Order) {
-        self.total_volume -= order.leaves_quantity;
-        self.hidden_volume -= order.hidden_quantity();
-        self.visible_volume -= order.visible_quantity;
+    fn unlink_order(&mut self, pool: &mut OrderPool, handle: OrderHandle) -> Result<(), ErrorCode> {
+        self.orders.remove(pool, handle)
     }
-    fn add_volumes(&mut self, order: &Order) {
-        self.total_volume += order.leaves_quantity;
-        self.hidden_volume += order.hidden_quantity();
-        self.visible_volume += order.visible_quantity;
-    }
-    fn unlink_order(&mut self, order: &Order) {
-        self.orders.pop_current(order); 
-    }
-    // Function to conditionally unlink an order from a level
-    fn conditional_unlink_order(&mut self, order: &Order){
-        if order.leaves_quantity == 0 {
-            self.unlink_order(order)
-        } else {
-        }
-    }
-    fn process_level(&mut self, order_book: &mut OrderBook, order: &mut Order) {
-        if self.total_volume == 0 {
-            // Assuming `delete_trailing_stop_level` is a method of OrderBook that requires a reference to Order
-            order_book.delete_trailing_stop_level(order);
-
-            // Assuming `level_node` is an Option for a linkage structure (like a node in a linked list or tree)
-            // and you want to remove it if certain conditions are met
-            // Here, we simply take() the Option, effectively removing it
-            order.level_node.take();
-        }
-    }
-    fn link_order(&mut self, order: &Order) {
-        todo!()
+    fn link_order(&mut self, pool: &mut OrderPool, handle: OrderHandle) -> Result<(), ErrorCode> {
+        self.orders.push_back(pool, handle)
     }
 }
 
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Level {
     pub price: u64,
     pub total_volume: u64,
     pub hidden_volume: u64,
     pub visible_volume: u64,
-    pub(crate) orders: LinkedList<Order>,
+    // Queued orders in time priority, linked through their order pool slots
+    pub(crate) orders: OrderList,
     pub level_type: LevelType,
 }
 
-impl From<Level> for LevelNode {
-    fn from(level: Level) -> Self {
-        LevelNode {
-            parent: None,
-            left: None,
-            right: None,
-            level:  level,
-        }
-    }
-}
-
 impl Level {
-    /// Creates a new `Level` instance.
-    /// 
-    /// The method returns `Pin<Box<Self>>` because `Level` instances might contain
-    /// self-referential data (e.g., parent, left, Aight raw pointers). `Pin` guarantees
-    /// that the `Level` instance will not be moved in memory, which is crucial for 
-    /// maintaining the validity of self-referential pointers.
-
+    // Creates an empty level of the given type and price
     pub fn with_price(level_type: LevelType, price: u64) -> Self {
         Level {
             price,
             total_volume: 0,  // Default value
             hidden_volume: 0, // Default value
             visible_volume: 0, // Default value
-            orders: LinkedList::new(), // Initialize with an empty LinkedList
+            orders: OrderList::default(), // No orders queued yet
             level_type,// Default value
-            // parent: None,  // No parent initially
-            // left: None,    // No left child initially
-            // right: None,   // No right child initially
-            // _pinned: PhantomPinned,
         }
     }
 
@@ -107,25 +53,12 @@ impl Level {
     pub fn is_ask(&self) -> bool {
         self.level_type == LevelType::Ask
     }
-    
 
-    pub fn add_volumes(&mut self, order: &Order) {
-        self.total_volume += order.leaves_quantity;
-        self.hidden_volume += order.hidden_quantity();
-        self.visible_volume += order.visible_quantity;
-    }
 
-    pub fn process_order(&mut self, order: &Order) -> Result<(), ErrorCode> {
-        self.add_volumes(order);
-        self.link_order(order);
-        self.orders.push_back(*order); // Directly pushing `order`, which now takes ownership
-        Ok(())
+    // Handles of the queued orders in time priority
+    pub fn queued_orders<'a>(&self, pool: &'a OrderPool) -> QueueIter<'a> {
+        self.orders.iter(pool)
     }
-
-    // pub fn link_order(&self, mut level: Level, order: &Order) {
-    //     level.orders.pop_current(&Order); // push_back for LinkedList
-    //   
-    // }
 }
 
 
@@ -141,36 +74,8 @@ pub struct LevelUpdate {
     pub(crate)top: bool,
 }
 
-pub trait PopCurrent<T> {
-    fn pop_current(&mut self, value: &T) -> Option<T>;
-}
-
-
-// impl From<Level> for LevelNode {
-//     fn from(value: Level) -> Self {
-//         Box::into_raw(Box::new(value))
-//     }
-// }
-
 
-impl<T: PartialEq> PopCurrent<T> for LinkedList<T> {
-    fn pop_current(&mut self, value: &T) -> Option<T> {
-        let mut new_list = LinkedList::new();
-        let mut removed_item: Option<T> = None;
-
-        while let Some(item) = self.pop_front() {
-            if item == *value && removed_item.is_none() {
-                removed_item = Some(item);
-            } else {
-                new_list.push_back(item);
-            }
-        }
-        *self = new_list;
-        removed_item
-    }
-}
-
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum LevelType {
     Bid,
     Ask,
@@ -192,36 +97,3 @@ impl PartialOrd for Level {
         self.price.partial_cmp(&other.price)
     }
 }
-
-impl Level {
-
-    pub fn new_as_ptr(price: u64, level: Level, parent: Level) -> Self {
-        Level {
-            orders: todo!(),
-            price,
-            total_volume: todo!(),
-            hidden_volume: todo!(),
-            visible_volume: todo!(),
-            level_type: todo!(),
-            // parent: todo!(),
-            // left: todo!(),
-            // right: todo!(),
-            // _pinned: PhantomPinned,
-        }
-    }
-
-    pub fn new_from_order(price: u64, level_type: LevelType) -> Self {
-        Level {
-            orders: todo!(),
-            price,
-            total_volume: todo!(),
-            hidden_volume: todo!(),
-            visible_volume: todo!(),
-            level_type,
-            // parent: todo!(),
-            // left: todo!(),
-            // right: todo!(),
-            // _pinned: PhantomPinned,
-        }
-    }
-}
\ No newline at end of file
@@ -1,4 +1,5 @@
 
 pub mod indexing;
+pub mod ladder;
 pub mod level;
-pub mod level_node;
+pub mod order_queue;
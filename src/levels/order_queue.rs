@@ -0,0 +1,90 @@
+use crate::orders::{order::ErrorCode, order_pool::{OrderHandle, OrderPool}};
+
+// Time priority queue of the orders at one price level. The queue is intrusive: orders
+// are linked through the prev/next handles kept in their order pool slots, so pushing,
+// removing and popping an order is O(1) without any storage of its own.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct OrderList {
+    head: Option<OrderHandle>,
+    tail: Option<OrderHandle>,
+    len: usize,
+}
+
+impl OrderList {
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn front(&self) -> Option<OrderHandle> {
+        self.head
+    }
+
+    // Orders are queued in one list at most, including lists of other levels
+    pub fn push_back(&mut self, pool: &mut OrderPool, handle: OrderHandle) -> Result<(), ErrorCode> {
+        if pool.is_queued(handle).ok_or(ErrorCode::OrderNotFound)? {
+            return Err(ErrorCode::OrderDuplicate);
+        }
+
+        pool.set_queued(handle, true);
+        pool.set_prev(handle, self.tail);
+        match self.tail {
+            Some(tail) => pool.set_next(tail, Some(handle)),
+            None => self.head = Some(handle),
+        }
+        self.tail = Some(handle);
+        self.len += 1;
+        Ok(())
+    }
+
+    // Unlinks the order from the list it is queued in
+    pub fn remove(&mut self, pool: &mut OrderPool, handle: OrderHandle) -> Result<(), ErrorCode> {
+        let (prev, next) = pool.links(handle).ok_or(ErrorCode::OrderNotFound)?;
+        if !pool.is_queued(handle).unwrap_or(false) || (prev.is_none() && self.head != Some(handle)) {
+            return Err(ErrorCode::OrderNotFound);
+        }
+
+        match prev {
+            Some(prev) => pool.set_next(prev, next),
+            None => self.head = next,
+        }
+        match next {
+            Some(next) => pool.set_prev(next, prev),
+            None => self.tail = prev,
+        }
+        pool.set_prev(handle, None);
+        pool.set_next(handle, None);
+        pool.set_queued(handle, false);
+        self.len -= 1;
+        Ok(())
+    }
+
+    // Unlinks the order and queues it again at the back of the list
+    pub fn move_to_back(&mut self, pool: &mut OrderPool, handle: OrderHandle) -> Result<(), ErrorCode> {
+        self.remove(pool, handle)?;
+        self.push_back(pool, handle)
+    }
+
+    // Order handles of the list in time priority
+    pub fn iter<'a>(&self, pool: &'a OrderPool) -> QueueIter<'a> {
+        QueueIter { pool, next: self.head }
+    }
+}
+
+pub struct QueueIter<'a> {
+    pool: &'a OrderPool,
+    next: Option<OrderHandle>,
+}
+
+impl Iterator for QueueIter<'_> {
+    type Item = OrderHandle;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let handle = self.next?;
+        self.next = self.pool.links(handle).and_then(|(_, next)| next);
+        Some(handle)
+    }
+}
@@ -1,7 +1,11 @@
 
-mod order_book;
-mod itch_handler;
-mod market_handler;
-mod market_executors;
-mod levels;
+pub mod order_book;
+pub mod itch_handler;
+pub mod market_handler;
+pub mod market_executors;
+pub mod levels;
 pub mod orders;
+pub mod symbols;
+pub mod replay;
+pub mod session;
+pub mod trades;
@@ -0,0 +1,1898 @@
+use std::collections::BTreeSet;
+
+use crate::{levels::{indexing::{LevelContainer, LevelHandle, LevelIndex, PriceLevels}, level::{Level, LevelType, LevelUpdate, UpdateType}}, market_handler::{ExecutionEvent, Handler, LevelEvent, OrderEvent}, order_book::{auction_book::AuctionType, order_book::OrderBook}, orders::{order::{ErrorCode, Order, OrderSide, OrderType, TimeInForce}, order_pool::{OrderHandle, OrderPool}, orders::{OrderOps, Orders}}, symbols::{symbol::Symbol, symbol_registry::{SymbolOps, SymbolRegistry}}};
+
+use super::order_book_operations::{OBMap, OrderBookContainer};
+
+// Default end of the trading day for 'DAY' orders, 16:00 in nanoseconds since midnight
+pub const DEFAULT_DAY_CLOSE: u64 = 16 * 60 * 60 * 1_000_000_000;
+
+// Fields of a pooled order at the top of a crossed book that matching decides on
+#[derive(Clone, Copy)]
+struct CrossedOrder {
+    id: u64,
+    price: u64,
+    visible_quantity: u64,
+    aon: bool,
+}
+
+// Market manager owns symbols, order books and orders, and reports every change
+// to its market handler. Matching is disabled by default so the manager can
+// mirror an already matched feed such as ITCH.
+pub struct MarketManager<H> {
+    pub symbols: SymbolRegistry,
+    pub order_books: OBMap,
+    pub orders: Orders,
+    pub handler: H,
+    matching: bool,
+    // Timestamp of the feed message being applied, reported with every event
+    timestamp: u64,
+    day_close: u64,
+    // Pending 'DAY'/'Good-Till-Date' expirations as (expiry timestamp, order Id)
+    expirations: BTreeSet<(u64, u64)>,
+}
+
+impl<H> Default for MarketManager<H>
+where
+    H: Handler + Default,
+{
+    fn default() -> Self {
+        MarketManager::new(H::default())
+    }
+}
+
+impl<H> MarketManager<H>
+where
+    H: Handler,
+{
+    pub fn new(handler: H) -> Self {
+        MarketManager {
+            symbols: SymbolRegistry::default(),
+            order_books: OBMap::default(),
+            orders: Orders::default(),
+            handler,
+            matching: false,
+            timestamp: 0,
+            day_close: DEFAULT_DAY_CLOSE,
+            expirations: BTreeSet::new(),
+        }
+    }
+
+    pub fn get_symbol(&self, id: u64) -> Result<&Symbol, ErrorCode> {
+        self.symbols.get_symbol(id)
+    }
+
+    pub fn get_order_book(&self, id: u64) -> Result<&OrderBook, ErrorCode> {
+        self.order_books.get(&id).ok_or(ErrorCode::OrderBookNotFound)
+    }
+
+    pub fn get_order(&self, id: u64) -> Result<&Order, ErrorCode> {
+        self.orders.get_order(id)
+    }
+
+    pub fn timestamp(&self) -> u64 {
+        self.timestamp
+    }
+
+    // Advances the engine clock and expires 'DAY'/'Good-Till-Date' orders it has crossed
+    pub fn set_timestamp(&mut self, timestamp: u64) -> Result<(), ErrorCode> {
+        self.timestamp = timestamp;
+        self.expire_orders()
+    }
+
+    pub fn day_close(&self) -> u64 {
+        self.day_close
+    }
+
+    // Moves the close of the trading day together with the pending 'DAY' expirations
+    pub fn set_day_close(&mut self, day_close: u64) -> Result<(), ErrorCode> {
+        let day_orders: Vec<u64> = self
+            .expirations
+            .range((self.day_close, 0)..=(self.day_close, u64::MAX))
+            .map(|&(_, id)| id)
+            .filter(|&id| matches!(self.orders.get_order(id), Ok(order) if order.is_day()))
+            .collect();
+        for id in day_orders {
+            self.expirations.remove(&(self.day_close, id));
+            self.expirations.insert((day_close, id));
+        }
+        self.day_close = day_close;
+
+        // An earlier close may have been reached already
+        self.expire_orders()
+    }
+
+    pub fn is_matching_enabled(&self) -> bool {
+        self.matching
+    }
+
+    // Enables automatic matching and matches all crossed order books
+    pub fn enable_matching(&mut self) -> Result<(), ErrorCode> {
+        self.matching = true;
+        self.match_orders()
+    }
+
+    pub fn disable_matching(&mut self) {
+        self.matching = false;
+    }
+
+    pub fn add_symbol(&mut self, symbol: Symbol) -> Result<(), ErrorCode> {
+        self.symbols.add_symbol(symbol.clone())?;
+
+        // Call the corresponding MarketHandler
+        self.handler.on_add_symbol(&symbol);
+
+        Ok(())
+    }
+
+    pub fn delete_symbol(&mut self, id: u64) -> Result<(), ErrorCode> {
+        let symbol = self.symbols.remove_symbol(id)?;
+
+        // Call the corresponding MarketHandler
+        self.handler.on_delete_symbol(&symbol);
+
+        Ok(())
+    }
+
+    pub fn add_order_book(&mut self, symbol: &Symbol) -> Result<(), ErrorCode> {
+        self.add_order_book_with_levels(symbol, LevelContainer::default())
+    }
+
+    // Adds an order book keeping its bids and asks in the given level container
+    pub fn add_order_book_with_levels(&mut self, symbol: &Symbol, container: LevelContainer) -> Result<(), ErrorCode> {
+        // Order books are only created for registered symbols
+        let symbol = self.symbols.get_symbol(symbol.id)?.clone();
+        let id = symbol.id;
+
+        self.order_books.add_order_book(id, OrderBook::with_levels(symbol, container))?;
+
+        // Call the corresponding MarketHandler
+        self.handler.on_add_order_book(self.order_books.get_order_book(&id)?);
+
+        Ok(())
+    }
+
+    pub fn delete_order_book(&mut self, id: u64) -> Result<(), ErrorCode> {
+        let order_book = self.order_books.remove_order_book(&id)?;
+
+        // Call the corresponding MarketHandler
+        self.handler.on_delete_order_book(&order_book);
+
+        Ok(())
+    }
+
+    pub fn add_order(&mut self, mut order: Order) -> Result<(), ErrorCode> {
+        // Validate order parameters
+        order.validate()?;
+
+        // 'DAY' and 'Good-Till-Date' orders must expire after the current engine time
+        if let Some(expiry) = self.expiry(&order) {
+            if expiry <= self.timestamp {
+                return Err(ErrorCode::OrderParameterInvalid);
+            }
+        }
+
+        // Display the first slice of an iceberg order
+        order.replenish();
+
+        // At-the-open and at-the-close orders wait for their cross
+        if let Some(auction) = order.auction() {
+            return self.add_auction_order(order, auction);
+        }
+
+        let matching = self.matching;
+        match order.order_type {
+            OrderType::Market => self.add_market_order(order, matching, false),
+            OrderType::Limit => self.add_limit_order(order, matching, false),
+            OrderType::Stop | OrderType::TrailingStop => self.add_stop_order(order, matching, false),
+            OrderType::StopLimit | OrderType::TrailingStopLimit => self.add_stop_limit_order(order, matching, false),
+            _ => Err(ErrorCode::OrderTypeInvalid),
+        }
+    }
+
+    pub fn reduce_order(&mut self, id: u64, quantity: u64) -> Result<(), ErrorCode> {
+        self.reduce_order_recursive(id, quantity, self.matching, false)
+    }
+
+    pub fn modify_order(&mut self, id: u64, new_price: u64, new_quantity: u64) -> Result<(), ErrorCode> {
+        self.modify_order_recursive(id, new_price, new_quantity, false, self.matching, false)
+    }
+
+    // Modifies the order with In-Flight Mitigation: quantity already executed
+    // is subtracted from the new quantity
+    pub fn mitigate_order(&mut self, id: u64, new_price: u64, new_quantity: u64) -> Result<(), ErrorCode> {
+        self.modify_order_recursive(id, new_price, new_quantity, true, self.matching, false)
+    }
+
+    pub fn replace_order(&mut self, id: u64, new_id: u64, new_price: u64, new_quantity: u64) -> Result<(), ErrorCode> {
+        self.replace_order_recursive(id, new_id, new_price, new_quantity, self.matching, false)
+    }
+
+    pub fn delete_order(&mut self, id: u64) -> Result<(), ErrorCode> {
+        self.delete_order_recursive(id, self.matching, false)
+    }
+
+    // Executes the order at its own price
+    pub fn execute_order(&mut self, id: u64, quantity: u64) -> Result<(), ErrorCode> {
+        let price = self.orders.get_order(id)?.price;
+        self.execute_order_with_price(id, price, quantity)
+    }
+
+    pub fn execute_order_with_price(&mut self, id: u64, price: u64, quantity: u64) -> Result<(), ErrorCode> {
+        // Validate parameters
+        if id == 0 {
+            return Err(ErrorCode::OrderIdInvalid);
+        }
+        if quantity == 0 {
+            return Err(ErrorCode::OrderQuantityInvalid);
+        }
+
+        let handle = self.orders.get_handle(id)?;
+        let order = self.orders.get_mut_order_at(handle)?;
+        let order_book = self.order_books.get_order_book(&order.symbol_id)?;
+
+        // Auction orders are only executed by their cross
+        if order.auction().is_some() {
+            return Err(ErrorCode::OrderParameterInvalid);
+        }
+
+        // Only orders resting in the book or its stop levels can be executed
+        if !matches!(order.order_type, OrderType::Limit | OrderType::Stop | OrderType::StopLimit | OrderType::TrailingStop | OrderType::TrailingStopLimit) {
+            return Err(ErrorCode::OrderTypeInvalid);
+        }
+
+        // Calculate the minimal possible order quantity to execute
+        let quantity = std::cmp::min(quantity, order.leaves_quantity);
+
+        // Feed executions hit the resting order, so the aggressor is on the other side
+        let aggressor = if order.is_buy() { OrderSide::Sell } else { OrderSide::Buy };
+
+        // Call the corresponding MarketHandler
+        self.handler.on_execute_order(ExecutionEvent { order, price, quantity, aggressor: Some(aggressor), counterparty: None, timestamp: self.timestamp });
+
+        // Update the corresponding market price, the trade printed for both sides
+        order_book.update_last_trade_price(price);
+        order_book.update_matching_price(order, price);
+
+        // Increase the order executed quantity and decrease its leaves quantity
+        let hidden = order.hidden_quantity();
+        let visible = order.visible_quantity();
+        order.execute(price, quantity);
+        order.reduce_quantity(quantity);
+        let hidden = hidden - order.hidden_quantity();
+        let visible = visible - order.visible_quantity();
+
+        // Reduce the order in the order book
+        let order_type = order.order_type;
+        let pool = self.orders.pool_mut();
+        match order_type {
+            OrderType::Limit => {
+                let update = order_book.reduce_order(pool, handle, quantity, hidden, visible)?;
+                Self::update_level(&mut self.handler, self.timestamp, order_book, update);
+                Self::replenish_order(&mut self.handler, self.timestamp, order_book, pool, handle)?;
+            },
+            OrderType::Stop | OrderType::StopLimit => {
+                order_book.reduce_stop_order(pool, handle, quantity, hidden, visible)?;
+            },
+            _ => {
+                order_book.reduce_trailing_stop_order(pool, handle, quantity, hidden, visible)?;
+            },
+        }
+
+        // Update the order or delete the empty order
+        let order = self.orders.get_order_at(handle)?;
+        let symbol_id = order.symbol_id;
+        if order.leaves_quantity > 0 {
+            // Call the corresponding MarketHandler
+            self.handler.on_update_order(OrderEvent { order, timestamp: self.timestamp });
+        } else {
+            // Call the corresponding MarketHandler
+            self.handler.on_delete_order(OrderEvent { order, timestamp: self.timestamp });
+
+            // Erase the order
+            self.orders.remove_order(&id);
+        }
+
+        // Automatic order matching, stop orders are triggered by the trade either way
+        if self.matching {
+            self.match_order_book(symbol_id)?;
+        } else {
+            self.activate_stop_orders(symbol_id)?;
+        }
+
+        // Reset matching price
+        self.order_books.get_order_book(&symbol_id)?.reset_matching_price();
+
+        Ok(())
+    }
+
+    // Uncrosses the auction at the price executing the largest volume. Auction orders
+    // trade with each other first and then with the continuous book, all of them at
+    // the uncross price. Anything the cross leaves unfilled is cancelled.
+    pub fn cross_auction(&mut self, symbol_id: u64, auction: AuctionType) -> Result<(), ErrorCode> {
+        // Halted, paused and quotation only books are never crossed, their auction orders keep waiting
+        if !self.order_books.get_order_book(&symbol_id)?.is_trading() {
+            return Ok(());
+        }
+
+        let ids = self.order_books.get_order_book(&symbol_id)?.auction_book(auction).take_orders();
+        let mut orders = ids
+            .iter()
+            .map(|id| self.orders.remove_order(id).ok_or(ErrorCode::OrderNotFound))
+            .collect::<Result<Vec<Order>, ErrorCode>>()?;
+
+        if let Some(price) = Self::uncross_price(self.order_books.get_order_book(&symbol_id)?, &orders) {
+            // Auction orders willing to trade at the uncross price, in time priority
+            let crossed = |order: &Order| order.is_market() || if order.is_buy() { order.price >= price } else { order.price <= price };
+            let buys: Vec<usize> = (0..orders.len()).filter(|&i| orders[i].is_buy() && crossed(&orders[i])).collect();
+            let sells: Vec<usize> = (0..orders.len()).filter(|&i| !orders[i].is_buy() && crossed(&orders[i])).collect();
+
+            // Match the auction buys against the auction sells
+            let (mut buy, mut sell) = (0, 0);
+            while buy < buys.len() && sell < sells.len() {
+                let (buy_id, sell_id) = (orders[buys[buy]].id, orders[sells[sell]].id);
+                let quantity = std::cmp::min(orders[buys[buy]].leaves_quantity, orders[sells[sell]].leaves_quantity);
+                self.execute_auction_order(&mut orders[buys[buy]], sell_id, price, quantity)?;
+                self.execute_auction_order(&mut orders[sells[sell]], buy_id, price, quantity)?;
+
+                if orders[buys[buy]].leaves_quantity == 0 {
+                    buy += 1;
+                }
+                if orders[sells[sell]].leaves_quantity == 0 {
+                    sell += 1;
+                }
+            }
+
+            // Match the rest of the auction orders against the continuous book
+            for &index in buys[buy..].iter().chain(&sells[sell..]) {
+                self.match_auction_order(&mut orders[index], price)?;
+            }
+        }
+
+        for order in &orders {
+            // Call the corresponding MarketHandler
+            if order.leaves_quantity > 0 {
+                self.handler.on_delete_unmatched_order(OrderEvent { order, timestamp: self.timestamp });
+            } else {
+                self.handler.on_delete_order(OrderEvent { order, timestamp: self.timestamp });
+            }
+        }
+
+        // Activate the stop orders triggered by the cross
+        self.match_order_book(symbol_id)?;
+
+        // Reset matching price
+        self.order_books.get_order_book(&symbol_id)?.reset_matching_price();
+
+        Ok(())
+    }
+
+    // Deletes every 'DAY'/'Good-Till-Date' order whose expiry the engine clock has reached
+    pub fn expire_orders(&mut self) -> Result<(), ErrorCode> {
+        while let Some(&(expiry, id)) = self.expirations.first() {
+            if expiry > self.timestamp {
+                break;
+            }
+            self.expirations.pop_first();
+
+            // Skip orders already filled, deleted or replaced since they were scheduled
+            match self.orders.get_order(id) {
+                Ok(order) if self.expiry(order) == Some(expiry) => {},
+                _ => continue,
+            }
+            self.delete_order_recursive(id, self.matching, false)?;
+        }
+        Ok(())
+    }
+
+    // Matches all crossed order books
+    pub fn match_orders(&mut self) -> Result<(), ErrorCode> {
+        let symbols: Vec<u64> = self.order_books.keys().copied().collect();
+        for symbol_id in symbols {
+            self.match_order_book(symbol_id)?;
+            self.order_books.get_order_book(&symbol_id)?.reset_matching_price();
+        }
+        Ok(())
+    }
+
+    fn expiry(&self, order: &Order) -> Option<u64> {
+        match order.time_in_force {
+            TimeInForce::DAY => Some(self.day_close),
+            TimeInForce::GTD(expiry) => Some(expiry),
+            _ => None,
+        }
+    }
+
+    fn schedule_expiry(&mut self, order: &Order) {
+        if let Some(expiry) = self.expiry(order) {
+            self.expirations.insert((expiry, order.id));
+        }
+    }
+
+    // Refills an iceberg order whose displayed slice was fully consumed. The refreshed
+    // slice goes to the back of the level queue, so only the visible volume changes.
+    fn replenish_order(handler: &mut H, timestamp: u64, order_book: &mut OrderBook, pool: &mut OrderPool, handle: OrderHandle) -> Result<(), ErrorCode> {
+        let order = pool.get_mut(handle).ok_or(ErrorCode::OrderNotFound)?;
+        if !order.needs_replenish() {
+            return Ok(());
+        }
+
+        order.replenish();
+        let quantity = order.visible_quantity();
+        let update = order_book.replenish_order(pool, handle, quantity)?;
+        Self::update_level(handler, timestamp, order_book, update);
+
+        Ok(())
+    }
+
+    fn update_level(handler: &mut H, timestamp: u64, order_book: &OrderBook, update: LevelUpdate) {
+        let event = LevelEvent { order_book, level: &update.update, top: update.top, timestamp };
+        match update.update_type {
+            UpdateType::Add => handler.on_add_level(event),
+            UpdateType::Update => handler.on_update_level(event),
+            UpdateType::Delete => handler.on_delete_level(event),
+        };
+        handler.on_update_order_book(order_book, update.top)
+    }
+
+    // Price executing the largest auction volume, ties go to the smaller imbalance and
+    // then to the lower price. Candidates are the auction limit prices and the book levels.
+    // Book levels count their whole volume, hidden iceberg reserves included, as
+    // replenished slices keep trading with the auction orders at the uncross price.
+    fn uncross_price(order_book: &OrderBook, orders: &[Order]) -> Option<u64> {
+        let mut prices: Vec<u64> = orders
+            .iter()
+            .filter(|order| !order.is_market())
+            .map(|order| order.price)
+            .chain(order_book.bids.iter().map(|level| level.price))
+            .chain(order_book.asks.iter().map(|level| level.price))
+            .collect();
+        prices.sort_unstable();
+        prices.dedup();
+
+        let mut best: Option<(u64, u64, u64)> = None;
+        for price in prices {
+            let buy = orders
+                .iter()
+                .filter(|order| order.is_buy() && (order.is_market() || order.price >= price))
+                .map(|order| order.leaves_quantity)
+                .sum::<u64>()
+                + order_book.bids.iter().take_while(|level| level.price >= price).map(|level| level.total_volume).sum::<u64>();
+            let sell = orders
+                .iter()
+                .filter(|order| !order.is_buy() && (order.is_market() || order.price <= price))
+                .map(|order| order.leaves_quantity)
+                .sum::<u64>()
+                + order_book.asks.iter().take_while(|level| level.price <= price).map(|level| level.total_volume).sum::<u64>();
+
+            let volume = std::cmp::min(buy, sell);
+            let imbalance = buy.abs_diff(sell);
+            if volume == 0 {
+                continue;
+            }
+            if !matches!(best, Some((_, best_volume, best_imbalance)) if volume < best_volume || (volume == best_volume && imbalance >= best_imbalance)) {
+                best = Some((price, volume, imbalance));
+            }
+        }
+
+        best.map(|(price, _, _)| price)
+    }
+
+    // Executes an auction order taken out of the auction book during its cross
+    fn execute_auction_order(&mut self, order: &mut Order, counterparty: u64, price: u64, quantity: u64) -> Result<(), ErrorCode> {
+        // Call the corresponding MarketHandler
+        self.handler.on_execute_order(ExecutionEvent { order, price, quantity, aggressor: None, counterparty: Some(counterparty), timestamp: self.timestamp });
+
+        // Update the corresponding market price
+        let order_book = self.order_books.get_order_book(&order.symbol_id)?;
+        order_book.update_last_price(order, price);
+        order_book.update_matching_price(order, price);
+
+        // Increase the order executed quantity and decrease its leaves quantity
+        order.execute(price, quantity);
+        order.leaves_quantity -= quantity;
+
+        Ok(())
+    }
+
+    // Matches an auction order against the continuous book levels crossed at the uncross price
+    fn match_auction_order(&mut self, order: &mut Order, price: u64) -> Result<(), ErrorCode> {
+        // Start matching from the best opposite price level
+        let level_type = if order.is_buy() { LevelType::Ask } else { LevelType::Bid };
+        let mut level = self.order_books.get_order_book(&order.symbol_id)?.levels(level_type).best();
+
+        // Travel through price levels while they are crossed at the uncross price
+        while let Some(current) = level {
+            let levels = self.order_books.get_order_book(&order.symbol_id)?.levels(level_type);
+            let Some(current_level) = levels.get(current) else {
+                break;
+            };
+            let crossed = if order.is_buy() { current_level.price <= price } else { current_level.price >= price };
+            if !crossed {
+                break;
+            }
+
+            // Find the next level before the current one is emptied by executions
+            level = levels.next(current);
+
+            // Replenished iceberg slices are queued again at the back of the level
+            let mut requeued = true;
+            while requeued {
+                requeued = false;
+
+                // The level is gone once all of its orders were executed
+                let order_book = self.order_books.get_order_book(&order.symbol_id)?;
+                let Some(current_level) = order_book.levels(level_type).get(current) else {
+                    break;
+                };
+                let executing = Self::queued_ids(&self.orders, current_level)?;
+
+                for executing_id in executing {
+                    let executing_order = self.orders.get_mut_order(executing_id)?;
+
+                    // Skip 'All-Or-None' orders the auction order cannot fill completely
+                    if executing_order.is_aon() && (executing_order.leaves_quantity > order.leaves_quantity) {
+                        continue;
+                    }
+
+                    // Only the displayed slice can be hit, iceberg reserves are requeued first
+                    let quantity = Self::executable_quantity(executing_order, order.leaves_quantity);
+
+                    // Call the corresponding MarketHandler
+                    self.handler.on_execute_order(ExecutionEvent { order: executing_order, price, quantity, aggressor: None, counterparty: Some(order.id), timestamp: self.timestamp });
+
+                    // Update the corresponding market price
+                    let order_book = self.order_books.get_order_book(&order.symbol_id)?;
+                    order_book.update_last_price(executing_order, price);
+                    order_book.update_matching_price(executing_order, price);
+
+                    // Increase the executing order executed quantity
+                    executing_order.execute(price, quantity);
+
+                    // Reduce the executing order in the order book, deleting the level once it is empty
+                    self.reduce_order_recursive(executing_id, quantity, true, true)?;
+
+                    self.execute_auction_order(order, executing_id, price, quantity)?;
+                    if order.leaves_quantity == 0 {
+                        return Ok(());
+                    }
+
+                    // The executing order is still resting only if its slice was replenished
+                    requeued |= self.orders.contains_key(&executing_id);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    // Ids of the orders queued in the level, collected before executions change the queue
+    fn queued_ids(orders: &Orders, level: &Level) -> Result<Vec<u64>, ErrorCode> {
+        level.queued_orders(orders.pool()).map(|handle| orders.get_order_at(handle).map(|order| order.id)).collect()
+    }
+
+    fn add_auction_order(&mut self, order: Order, auction: AuctionType) -> Result<(), ErrorCode> {
+        let order_book = self.order_books.get_order_book(&order.symbol_id)?;
+
+        if self.orders.contains_key(&order.id) {
+            return Err(ErrorCode::OrderDuplicate);
+        }
+        self.orders.insert_order(&order)?;
+        order_book.auction_book(auction).add_order(order.id);
+
+        // Call the corresponding MarketHandler
+        self.handler.on_add_order(OrderEvent { order: &order, timestamp: self.timestamp });
+
+        Ok(())
+    }
+
+    fn add_market_order(&mut self, mut order: Order, matching: bool, recursive: bool) -> Result<(), ErrorCode> {
+        let symbol_id = order.symbol_id;
+
+        // Get the valid order book for the order
+        let order_book = self.order_books.get_order_book(&symbol_id)?;
+
+        // Market orders need liquidity on the opposite side
+        let liquidity = if order.is_buy() { order_book.best_ask() } else { order_book.best_bid() };
+        if liquidity.is_none() {
+            return Err(ErrorCode::NoLiquidity);
+        }
+
+        // Call the corresponding MarketHandler
+        self.handler.on_add_order(OrderEvent { order: &order, timestamp: self.timestamp });
+
+        // Automatic order matching
+        if matching && !recursive {
+            self.match_market(&mut order)?;
+        }
+
+        // Market orders never rest, so the remaining part beyond the slippage bound is cancelled
+        if order.leaves_quantity > 0 {
+            // Call the corresponding MarketHandler
+            self.handler.on_delete_unmatched_order(OrderEvent { order: &order, timestamp: self.timestamp });
+        } else {
+            // Call the corresponding MarketHandler
+            self.handler.on_delete_order(OrderEvent { order: &order, timestamp: self.timestamp });
+        }
+
+        // Automatic order matching
+        if matching && !recursive {
+            self.match_order_book(symbol_id)?;
+        }
+
+        // Trailing stop orders follow the best prices
+        if !recursive {
+            self.recalculate_trailing_stop_prices(symbol_id)?;
+        }
+
+        // Reset matching price
+        self.order_books.get_order_book(&symbol_id)?.reset_matching_price();
+
+        Ok(())
+    }
+
+    fn add_limit_order(&mut self, mut order: Order, matching: bool, recursive: bool) -> Result<(), ErrorCode> {
+        let symbol_id = order.symbol_id;
+
+        // Check the order duplicate before anything is matched or reported
+        if self.orders.contains_key(&order.id) {
+            return Err(ErrorCode::OrderDuplicate);
+        }
+
+        // Get the valid order book for the order and check the order fits its levels
+        self.order_books.get_order_book(&symbol_id)?.validate_price(order.price)?;
+
+        // Call the corresponding MarketHandler
+        self.handler.on_add_order(OrderEvent { order: &order, timestamp: self.timestamp });
+
+        // Automatic order matching
+        if matching && !recursive {
+            self.match_limit(&mut order)?;
+        }
+
+        // Add a new limit order or delete remaining part in case of 'Immediate-Or-Cancel'/'Fill-Or-Kill' order
+        if order.leaves_quantity > 0 && !order.is_ioc() && !order.is_fok() {
+            let handle = self.orders.insert_order(&order)?;
+
+            // Add the new limit order into the order book
+            let order_book = self.order_books.get_order_book(&symbol_id)?;
+            let update = order_book.add_order(self.orders.pool_mut(), handle)?;
+            Self::update_level(&mut self.handler, self.timestamp, order_book, update);
+            self.schedule_expiry(&order);
+        } else if order.leaves_quantity > 0 {
+            // Call the corresponding MarketHandler
+            self.handler.on_delete_unmatched_order(OrderEvent { order: &order, timestamp: self.timestamp });
+        } else {
+            // Call the corresponding MarketHandler
+            self.handler.on_delete_order(OrderEvent { order: &order, timestamp: self.timestamp });
+        }
+
+        // Automatic order matching
+        if matching && !recursive {
+            self.match_order_book(symbol_id)?;
+        }
+
+        // Trailing stop orders follow the best prices
+        if !recursive {
+            self.recalculate_trailing_stop_prices(symbol_id)?;
+        }
+
+        // Reset matching price
+        self.order_books.get_order_book(&symbol_id)?.reset_matching_price();
+
+        Ok(())
+    }
+
+    fn add_stop_order(&mut self, mut order: Order, matching: bool, recursive: bool) -> Result<(), ErrorCode> {
+        let symbol_id = order.symbol_id;
+
+        // Check the order duplicate before anything is matched or reported
+        if self.orders.contains_key(&order.id) {
+            return Err(ErrorCode::OrderDuplicate);
+        }
+
+        let order_book = self.order_books.get_order_book(&symbol_id)?;
+
+        // Recalculate stop price for trailing stop orders
+        if order.is_trailing_stop() || order.is_trailing_stop_limit() {
+            order.stop_price = order_book.calculate_trailing_stop_price(&order)?;
+        }
+
+        // Call the corresponding MarketHandler
+        self.handler.on_add_order(OrderEvent { order: &order, timestamp: self.timestamp });
+
+        // Automatic order matching
+        if matching && !recursive {
+            // Find the last trade price to trigger the stop order
+            let stop_price = if order.is_buy() {
+                order_book.get_last_trade_price_bid()
+            } else {
+                order_book.get_last_trade_price_ask()
+            };
+
+            // Check the arbitrage bid/ask prices
+            let arbitrage = if order.is_buy() {
+                order.stop_price <= stop_price
+            } else {
+                order.stop_price >= stop_price
+            };
+
+            if arbitrage {
+                // Convert the stop order into the market order
+                order.order_type = OrderType::Market;
+                order.price = 0;
+                order.stop_price = 0;
+                order.time_in_force = if order.is_fok() { TimeInForce::FOK } else { TimeInForce::IOC };
+
+                // Call the corresponding MarketHandler
+                self.handler.on_update_order(OrderEvent { order: &order, timestamp: self.timestamp });
+
+                // Match the market order
+                self.match_market(&mut order)?;
+
+                // Cancel the remaining part of the market order
+                if order.leaves_quantity > 0 {
+                    // Call the corresponding MarketHandler
+                    self.handler.on_delete_unmatched_order(OrderEvent { order: &order, timestamp: self.timestamp });
+                } else {
+                    // Call the corresponding MarketHandler
+                    self.handler.on_delete_order(OrderEvent { order: &order, timestamp: self.timestamp });
+                }
+
+                // Automatic order matching
+                self.match_order_book(symbol_id)?;
+
+                // Reset matching price
+                self.order_books.get_order_book(&symbol_id)?.reset_matching_price();
+
+                return Ok(());
+            }
+        }
+
+        // Add a new stop order
+        if order.leaves_quantity > 0 {
+            let handle = self.orders.insert_order(&order)?;
+
+            // Add the new stop order into the order book
+            let order_book = self.order_books.get_order_book(&symbol_id)?;
+            if order.is_trailing_stop() || order.is_trailing_stop_limit() {
+                order_book.add_trailing_stop_order(self.orders.pool_mut(), handle)?;
+            } else {
+                order_book.add_stop_order(self.orders.pool_mut(), handle)?;
+            }
+            self.schedule_expiry(&order);
+        } else {
+            // Call the corresponding MarketHandler
+            self.handler.on_delete_order(OrderEvent { order: &order, timestamp: self.timestamp });
+        }
+
+        // Automatic order matching
+        if matching && !recursive {
+            self.match_order_book(symbol_id)?;
+        }
+
+        // Trailing stop orders follow the best prices
+        if !recursive {
+            self.recalculate_trailing_stop_prices(symbol_id)?;
+        }
+
+        // Reset matching price
+        self.order_books.get_order_book(&symbol_id)?.reset_matching_price();
+
+        Ok(())
+    }
+
+    fn add_stop_limit_order(&mut self, mut order: Order, matching: bool, recursive: bool) -> Result<(), ErrorCode> {
+        let symbol_id = order.symbol_id;
+
+        // Check the order duplicate before anything is matched or reported
+        if self.orders.contains_key(&order.id) {
+            return Err(ErrorCode::OrderDuplicate);
+        }
+
+        let order_book = self.order_books.get_order_book(&symbol_id)?;
+
+        // Recalculate stop price for trailing stop orders
+        if order.is_trailing_stop() || order.is_trailing_stop_limit() {
+            let old_stop_price = order.stop_price;
+            order.stop_price = order_book.calculate_trailing_stop_price(&order)?;
+
+            // The limit price keeps its distance to the stop price
+            order.price = if order.stop_price > old_stop_price {
+                order.price.saturating_add(order.stop_price - old_stop_price)
+            } else {
+                order.price.saturating_sub(old_stop_price - order.stop_price)
+            };
+        }
+
+        // The limit price must fit the levels the order rests at once activated
+        order_book.validate_price(order.price)?;
+
+        // Call the corresponding MarketHandler
+        self.handler.on_add_order(OrderEvent { order: &order, timestamp: self.timestamp });
+
+        // Automatic order matching
+        if matching && !recursive {
+            // Find the last trade price to trigger the stop-limit order
+            let stop_price = if order.is_buy() {
+                order_book.get_last_trade_price_bid()
+            } else {
+                order_book.get_last_trade_price_ask()
+            };
+
+            // Check the arbitrage bid/ask prices
+            let arbitrage = if order.is_buy() {
+                order.stop_price <= stop_price
+            } else {
+                order.stop_price >= stop_price
+            };
+
+            if arbitrage {
+                // Convert the stop-limit order into the limit order
+                order.order_type = OrderType::Limit;
+                order.stop_price = 0;
+
+                // Call the corresponding MarketHandler
+                self.handler.on_update_order(OrderEvent { order: &order, timestamp: self.timestamp });
+
+                // Match the limit order and rest the remaining part
+                return self.add_limit_order(order, matching, recursive);
+            }
+        }
+
+        // Add a new stop order
+        if order.leaves_quantity > 0 {
+            let handle = self.orders.insert_order(&order)?;
+
+            // Add the new stop order into the order book
+            let order_book = self.order_books.get_order_book(&symbol_id)?;
+            if order.is_trailing_stop() || order.is_trailing_stop_limit() {
+                order_book.add_trailing_stop_order(self.orders.pool_mut(), handle)?;
+            } else {
+                order_book.add_stop_order(self.orders.pool_mut(), handle)?;
+            }
+            self.schedule_expiry(&order);
+        } else {
+            // Call the corresponding MarketHandler
+            self.handler.on_delete_order(OrderEvent { order: &order, timestamp: self.timestamp });
+        }
+
+        // Automatic order matching
+        if matching && !recursive {
+            self.match_order_book(symbol_id)?;
+        }
+
+        // Trailing stop orders follow the best prices
+        if !recursive {
+            self.recalculate_trailing_stop_prices(symbol_id)?;
+        }
+
+        // Reset matching price
+        self.order_books.get_order_book(&symbol_id)?.reset_matching_price();
+
+        Ok(())
+    }
+
+    fn reduce_order_recursive(&mut self, id: u64, quantity: u64, matching: bool, recursive: bool) -> Result<(), ErrorCode> {
+        // Validate parameters
+        if id == 0 {
+            return Err(ErrorCode::OrderIdInvalid);
+        }
+        if quantity == 0 {
+            return Err(ErrorCode::OrderQuantityInvalid);
+        }
+
+        let handle = self.orders.get_handle(id)?;
+        let order = self.orders.get_mut_order_at(handle)?;
+        let order_book = self.order_books.get_order_book(&order.symbol_id)?;
+
+        // Calculate the minimal possible order quantity to reduce
+        let quantity = std::cmp::min(quantity, order.leaves_quantity);
+
+        // Decrease the order leaves quantity
+        let hidden = order.hidden_quantity();
+        let visible = order.visible_quantity();
+        order.reduce_quantity(quantity);
+        let hidden = hidden - order.hidden_quantity();
+        let visible = visible - order.visible_quantity();
+
+        // Reduce the order in the order book
+        let (auction, order_type, leaves_quantity) = (order.auction(), order.order_type, order.leaves_quantity);
+        let pool = self.orders.pool_mut();
+        match (auction, order_type) {
+            (Some(auction), _) => {
+                if leaves_quantity == 0 {
+                    order_book.auction_book(auction).delete_order(id)?;
+                }
+            },
+            (None, OrderType::Limit) => {
+                let update = order_book.reduce_order(pool, handle, quantity, hidden, visible)?;
+                Self::update_level(&mut self.handler, self.timestamp, order_book, update);
+                Self::replenish_order(&mut self.handler, self.timestamp, order_book, pool, handle)?;
+            },
+            (None, OrderType::Stop | OrderType::StopLimit) => {
+                order_book.reduce_stop_order(pool, handle, quantity, hidden, visible)?;
+            },
+            (None, OrderType::TrailingStop | OrderType::TrailingStopLimit) => {
+                order_book.reduce_trailing_stop_order(pool, handle, quantity, hidden, visible)?;
+            },
+            _ => return Err(ErrorCode::OrderTypeInvalid),
+        };
+
+        // Update the order or delete the empty order
+        let order = self.orders.get_order_at(handle)?;
+        let symbol_id = order.symbol_id;
+        if order.leaves_quantity > 0 {
+            // Call the corresponding MarketHandler
+            self.handler.on_update_order(OrderEvent { order, timestamp: self.timestamp });
+        } else {
+            // Call the corresponding MarketHandler
+            self.handler.on_delete_order(OrderEvent { order, timestamp: self.timestamp });
+
+            // Erase the order
+            self.orders.remove_order(&id);
+        }
+
+        // Automatic order matching
+        if matching && !recursive {
+            self.match_order_book(symbol_id)?;
+        }
+
+        // Trailing stop orders follow the best prices
+        if !recursive {
+            self.recalculate_trailing_stop_prices(symbol_id)?;
+        }
+
+        // Reset matching price
+        self.order_books.get_order_book(&symbol_id)?.reset_matching_price();
+
+        Ok(())
+    }
+
+    fn modify_order_recursive(&mut self, id: u64, new_price: u64, new_quantity: u64, mitigate: bool, matching: bool, recursive: bool) -> Result<(), ErrorCode> {
+        // Validate parameters
+        if id == 0 {
+            return Err(ErrorCode::OrderIdInvalid);
+        }
+        if new_quantity == 0 {
+            return Err(ErrorCode::OrderQuantityInvalid);
+        }
+
+        let handle = self.orders.get_handle(id)?;
+        let order = self.orders.get_mut_order_at(handle)?;
+
+        // Only limit orders can be modified in place
+        if order.order_type != OrderType::Limit {
+            return Err(ErrorCode::OrderTypeInvalid);
+        }
+        if order.auction().is_some() {
+            return Err(ErrorCode::OrderParameterInvalid);
+        }
+
+        // Delete the order from the order book
+        let order_book = self.order_books.get_order_book(&order.symbol_id)?;
+        order_book.validate_price(new_price)?;
+        let update = order_book.delete_order(self.orders.pool_mut(), handle)?;
+        Self::update_level(&mut self.handler, self.timestamp, order_book, update);
+
+        // Modify the order
+        let order = self.orders.get_mut_order_at(handle)?;
+        order.price = new_price;
+        order.quantity = new_quantity;
+        order.leaves_quantity = new_quantity;
+
+        // In-Flight Mitigation (IFM)
+        if mitigate {
+            order.leaves_quantity = new_quantity.saturating_sub(order.executed_quantity);
+        }
+        order.replenish();
+
+        // Update the order or delete the empty order
+        let symbol_id = order.symbol_id;
+        if order.leaves_quantity > 0 {
+            // Call the corresponding MarketHandler
+            self.handler.on_update_order(OrderEvent { order, timestamp: self.timestamp });
+
+            // Add the modified order into the order book
+            let update = order_book.add_order(self.orders.pool_mut(), handle)?;
+            Self::update_level(&mut self.handler, self.timestamp, order_book, update);
+        } else {
+            // Call the corresponding MarketHandler
+            self.handler.on_delete_order(OrderEvent { order, timestamp: self.timestamp });
+
+            // Erase the order
+            self.orders.remove_order(&id);
+        }
+
+        // Automatic order matching
+        if matching && !recursive {
+            self.match_order_book(symbol_id)?;
+        }
+
+        // Trailing stop orders follow the best prices
+        if !recursive {
+            self.recalculate_trailing_stop_prices(symbol_id)?;
+        }
+
+        // Reset matching price
+        self.order_books.get_order_book(&symbol_id)?.reset_matching_price();
+
+        Ok(())
+    }
+
+    fn replace_order_recursive(&mut self, id: u64, new_id: u64, new_price: u64, new_quantity: u64, matching: bool, recursive: bool) -> Result<(), ErrorCode> {
+        // Validate parameters
+        if id == 0 || new_id == 0 {
+            return Err(ErrorCode::OrderIdInvalid);
+        }
+        if new_quantity == 0 {
+            return Err(ErrorCode::OrderQuantityInvalid);
+        }
+        if self.orders.contains_key(&new_id) {
+            return Err(ErrorCode::OrderDuplicate);
+        }
+        if self.orders.get_order(id)?.auction().is_some() {
+            return Err(ErrorCode::OrderParameterInvalid);
+        }
+
+        // Delete the previous order from the order book
+        let handle = self.orders.get_handle(id)?;
+        let order = self.orders.get_order_at(handle)?;
+        let (symbol_id, order_type) = (order.symbol_id, order.order_type);
+        let order_book = self.order_books.get_order_book(&symbol_id)?;
+        if matches!(order_type, OrderType::Limit | OrderType::StopLimit | OrderType::TrailingStopLimit) {
+            order_book.validate_price(new_price)?;
+        }
+        let pool = self.orders.pool_mut();
+        match order_type {
+            OrderType::Limit => {
+                let update = order_book.delete_order(pool, handle)?;
+                Self::update_level(&mut self.handler, self.timestamp, order_book, update);
+            },
+            OrderType::Stop | OrderType::StopLimit => {
+                order_book.delete_stop_order(pool, handle)?;
+            },
+            OrderType::TrailingStop | OrderType::TrailingStopLimit => {
+                order_book.delete_trailing_stop_order(pool, handle)?;
+            },
+            _ => return Err(ErrorCode::OrderTypeInvalid),
+        };
+
+        // Erase the previous order by Id
+        let mut order = self.orders.remove_order(&id).ok_or(ErrorCode::OrderNotFound)?;
+
+        // Call the corresponding MarketHandler
+        self.handler.on_delete_order(OrderEvent { order: &order, timestamp: self.timestamp });
+
+        // Replace the order keeping its symbol, side and type. Stop orders trigger at the
+        // new price, stop-limit orders keep their stop price and get a new limit price.
+        order.id = new_id;
+        match order.order_type {
+            OrderType::Stop | OrderType::TrailingStop => order.stop_price = new_price,
+            _ => order.price = new_price,
+        }
+        order.quantity = new_quantity;
+        order.executed_quantity = 0;
+        order.executed_value = 0;
+        order.leaves_quantity = new_quantity;
+        order.replenish();
+
+        // Call the corresponding MarketHandler
+        self.handler.on_add_order(OrderEvent { order: &order, timestamp: self.timestamp });
+
+        // Add the replaced order into the order book
+        let handle = self.orders.insert_order(&order)?;
+        let pool = self.orders.pool_mut();
+        match order.order_type {
+            OrderType::Limit => {
+                let update = order_book.add_order(pool, handle)?;
+                Self::update_level(&mut self.handler, self.timestamp, order_book, update);
+            },
+            OrderType::Stop | OrderType::StopLimit => {
+                order_book.add_stop_order(pool, handle)?;
+            },
+            _ => {
+                order_book.add_trailing_stop_order(pool, handle)?;
+            },
+        };
+        self.schedule_expiry(&order);
+
+        // Automatic order matching
+        if matching && !recursive {
+            self.match_order_book(symbol_id)?;
+        }
+
+        // Trailing stop orders follow the best prices
+        if !recursive {
+            self.recalculate_trailing_stop_prices(symbol_id)?;
+        }
+
+        // Reset matching price
+        self.order_books.get_order_book(&symbol_id)?.reset_matching_price();
+
+        Ok(())
+    }
+
+    fn delete_order_recursive(&mut self, id: u64, matching: bool, recursive: bool) -> Result<(), ErrorCode> {
+        // Validate parameters
+        if id == 0 {
+            return Err(ErrorCode::OrderIdInvalid);
+        }
+
+        let handle = self.orders.get_handle(id)?;
+        let order = self.orders.get_order_at(handle)?;
+        let (symbol_id, auction, order_type) = (order.symbol_id, order.auction(), order.order_type);
+        let order_book = self.order_books.get_order_book(&symbol_id)?;
+
+        // Delete the order from the order book
+        let pool = self.orders.pool_mut();
+        match (auction, order_type) {
+            (Some(auction), _) => {
+                order_book.auction_book(auction).delete_order(id)?;
+            },
+            (None, OrderType::Limit) => {
+                let update = order_book.delete_order(pool, handle)?;
+                Self::update_level(&mut self.handler, self.timestamp, order_book, update);
+            },
+            (None, OrderType::Stop | OrderType::StopLimit) => {
+                order_book.delete_stop_order(pool, handle)?;
+            },
+            (None, OrderType::TrailingStop | OrderType::TrailingStopLimit) => {
+                order_book.delete_trailing_stop_order(pool, handle)?;
+            },
+            _ => return Err(ErrorCode::OrderTypeInvalid),
+        };
+
+        // Erase the order
+        let order = self.orders.remove_order(&id).ok_or(ErrorCode::OrderNotFound)?;
+
+        // Call the corresponding MarketHandler
+        self.handler.on_delete_order(OrderEvent { order: &order, timestamp: self.timestamp });
+
+        // Automatic order matching
+        if matching && !recursive {
+            self.match_order_book(symbol_id)?;
+        }
+
+        // Trailing stop orders follow the best prices
+        if !recursive {
+            self.recalculate_trailing_stop_prices(symbol_id)?;
+        }
+
+        // Reset matching price
+        self.order_books.get_order_book(&symbol_id)?.reset_matching_price();
+
+        Ok(())
+    }
+
+    fn match_market(&mut self, order: &mut Order) -> Result<(), ErrorCode> {
+        let order_book = self.order_books.get_order_book(&order.symbol_id)?;
+
+        // Nothing to match when the opposite side is empty
+        let Some(best) = (if order.is_buy() { order_book.best_ask() } else { order_book.best_bid() }) else {
+            return Ok(());
+        };
+
+        // Calculate acceptable market order price with optional slippage value
+        let price = best.price;
+        order.price = if order.is_buy() {
+            price.saturating_add(order.slippage)
+        } else {
+            price.saturating_sub(order.slippage)
+        };
+
+        self.match_order(order)
+    }
+
+    fn match_limit(&mut self, order: &mut Order) -> Result<(), ErrorCode> {
+        // Match the limit order
+        self.match_order(order)
+    }
+
+    fn match_order(&mut self, order: &mut Order) -> Result<(), ErrorCode> {
+        let order_book = self.order_books.get_order_book(&order.symbol_id)?;
+
+        // Halted, paused and quotation only books are never crossed
+        if !order_book.is_trading() {
+            return Ok(());
+        }
+
+        // Start matching from the best opposite price level
+        let level_type = if order.is_buy() { LevelType::Ask } else { LevelType::Bid };
+        let mut level = order_book.levels(level_type).best();
+
+        // Special case for 'Fill-Or-Kill'/'All-Or-None' order
+        if order.is_fok() || order.is_aon() {
+            let Some(best) = level.and_then(|best| order_book.levels(level_type).get(best)) else {
+                return Ok(());
+            };
+            if !Self::is_crossed(order, best.price) {
+                return Ok(());
+            }
+
+            let chain = self.calculate_matching_chain_single_level(order.symbol_id, level_type, order.price, order.leaves_quantity)?;
+            if chain == 0 {
+                return Ok(());
+            }
+
+            // The whole chain is known to be fillable, so it is executed at once
+            let value = self.execute_matching_chain(order.symbol_id, level_type, order.price, chain, Some(&*order))?;
+
+            // Increase the order executed quantity
+            order.executed_quantity += chain;
+            order.executed_value += value;
+            order.leaves_quantity -= chain;
+
+            return Ok(());
+        }
+
+        // Travel through price levels while they cross the order price
+        while let Some(current) = level {
+            let levels = self.order_books.get_order_book(&order.symbol_id)?.levels(level_type);
+            let Some(current_level) = levels.get(current) else {
+                return Ok(());
+            };
+            if !Self::is_crossed(order, current_level.price) {
+                return Ok(());
+            }
+
+            // Find the next level before the current one is emptied by executions
+            level = levels.next(current);
+
+            // Replenished iceberg slices are queued again at the back of the level,
+            // so the level is traveled until none of its orders were requeued
+            let mut requeued = true;
+            while requeued {
+                requeued = false;
+
+                // The level is gone once all of its orders were executed
+                let order_book = self.order_books.get_order_book(&order.symbol_id)?;
+                let Some(current_level) = order_book.levels(level_type).get(current) else {
+                    break;
+                };
+                let executing = Self::queued_ids(&self.orders, current_level)?;
+
+                // Execute crossed orders in time priority
+                for executing_id in executing {
+                    let executing_order = self.orders.get_mut_order(executing_id)?;
+
+                    // Skip 'All-Or-None' orders the incoming order cannot fill completely
+                    if executing_order.is_aon() && (executing_order.leaves_quantity > order.leaves_quantity) {
+                        continue;
+                    }
+
+                    // Get the execution price and quantity, only the displayed slice can be hit
+                    let price = executing_order.price;
+                    let quantity = Self::executable_quantity(executing_order, order.leaves_quantity);
+
+                    // Call the corresponding MarketHandler
+                    self.handler.on_execute_order(ExecutionEvent { order: executing_order, price, quantity, aggressor: Some(order.order_side), counterparty: Some(order.id), timestamp: self.timestamp });
+
+                    // Update the corresponding market price
+                    let order_book = self.order_books.get_order_book(&order.symbol_id)?;
+                    order_book.update_last_price(executing_order, price);
+                    order_book.update_matching_price(executing_order, price);
+
+                    // Increase the executing order executed quantity
+                    executing_order.execute(price, quantity);
+
+                    // Reduce the executing order in the order book, deleting the level once it is empty
+                    self.reduce_order_recursive(executing_id, quantity, true, true)?;
+
+                    // Update the corresponding market price
+                    let order_book = self.order_books.get_order_book(&order.symbol_id)?;
+                    order_book.update_last_price(order, price);
+                    order_book.update_matching_price(order, price);
+
+                    // Increase the order executed quantity and decrease its leaves quantity
+                    order.execute(price, quantity);
+                    order.leaves_quantity -= quantity;
+                    if order.leaves_quantity == 0 {
+                        return Ok(());
+                    }
+
+                    // The executing order is still resting only if its slice was replenished
+                    requeued |= self.orders.contains_key(&executing_id);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    // Checks the arbitrage bid/ask prices between the order and the opposite level
+    fn is_crossed(order: &Order, level_price: u64) -> bool {
+        if order.is_buy() {
+            order.price >= level_price
+        } else {
+            order.price <= level_price
+        }
+    }
+
+    // Quantity of the resting order tradable against the given volume in one pass.
+    // 'All-Or-None' orders trade completely, others up to their displayed slice.
+    fn executable_quantity(order: &Order, volume: u64) -> u64 {
+        if order.is_aon() {
+            order.leaves_quantity
+        } else {
+            std::cmp::min(order.visible_quantity(), volume)
+        }
+    }
+
+    // Quantity of the resting order a matching chain can count on. Iceberg reserves are
+    // replenished while the chain is executed, so their whole leaves quantity counts.
+    fn chain_quantity(order: &Order, volume: u64) -> u64 {
+        if order.is_aon() {
+            order.leaves_quantity
+        } else {
+            std::cmp::min(order.leaves_quantity, volume)
+        }
+    }
+
+    fn match_order_book(&mut self, symbol_id: u64) -> Result<(), ErrorCode> {
+        // Halted, paused and quotation only books are never crossed
+        if !self.order_books.get_order_book(&symbol_id)?.is_trading() {
+            return Ok(());
+        }
+
+        loop {
+            // Match while the best bid crosses the best ask
+            loop {
+                let order_book = self.order_books.get_order_book(&symbol_id)?;
+                let (bid_level, ask_level) = match (order_book.best_bid(), order_book.best_ask()) {
+                    (Some(bid), Some(ask)) => (bid, ask),
+                    // Nothing to match
+                    _ => break,
+                };
+
+                let (bid_price, bid_order, ask_price, ask_order) = (
+                    bid_level.price,
+                    bid_level.queued_orders(self.orders.pool()).next(),
+                    ask_level.price,
+                    ask_level.queued_orders(self.orders.pool()).next(),
+                );
+
+                // Stop when the book is no longer crossed
+                if bid_price < ask_price {
+                    break;
+                }
+
+                let (Some(bid_handle), Some(ask_handle)) = (bid_order, ask_order) else {
+                    break;
+                };
+                let bid_order = self.crossed_order(bid_handle)?;
+                let ask_order = self.crossed_order(ask_handle)?;
+
+                // Special case for 'All-Or-None' orders
+                if bid_order.aon || ask_order.aon {
+                    // Calculate the matching chain
+                    let chain = self.calculate_matching_chain_cross_levels(symbol_id)?;
+
+                    // Matching is not possible
+                    if chain == 0 {
+                        break;
+                    }
+
+                    // Execute matching chains
+                    if bid_order.aon {
+                        self.execute_matching_chain(symbol_id, LevelType::Bid, bid_price, chain, None)?;
+                        self.execute_matching_chain(symbol_id, LevelType::Ask, bid_price, chain, None)?;
+                    } else {
+                        self.execute_matching_chain(symbol_id, LevelType::Ask, ask_price, chain, None)?;
+                        self.execute_matching_chain(symbol_id, LevelType::Bid, ask_price, chain, None)?;
+                    }
+                    continue;
+                }
+
+                // Find the best order to execute and the best order to reduce. Only the
+                // displayed slices trade in one pass, iceberg reserves are replenished
+                // and requeued before they trade again.
+                let (executing_order, reducing_order) = if bid_order.visible_quantity > ask_order.visible_quantity {
+                    (ask_order, bid_order)
+                } else {
+                    (bid_order, ask_order)
+                };
+
+                // Get the execution quantity and price
+                let quantity = executing_order.visible_quantity;
+                let price = executing_order.price;
+                if quantity == 0 {
+                    break;
+                }
+
+                // Execute the executing order and reduce the reducing order
+                self.execute_order_recursive(executing_order.id, price, quantity, None)?;
+                self.execute_order_recursive(reducing_order.id, price, quantity, None)?;
+            }
+
+            // Activated stop orders may trade and trigger further stop orders,
+            // so the book is matched again until no more stop orders are activated
+            if !self.activate_stop_orders(symbol_id)? {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
+    // Copies the fields of the pooled order matching decides on, so executions are free
+    // to update the pool
+    fn crossed_order(&self, handle: OrderHandle) -> Result<CrossedOrder, ErrorCode> {
+        let order = self.orders.get_order_at(handle)?;
+        Ok(CrossedOrder {
+            id: order.id,
+            price: order.price,
+            visible_quantity: order.visible_quantity(),
+            aon: order.is_aon(),
+        })
+    }
+
+    // Executes a resting order during matching without triggering nested matching.
+    // The taker is the incoming order the resting order is matched against, if any.
+    fn execute_order_recursive(&mut self, id: u64, price: u64, quantity: u64, taker: Option<&Order>) -> Result<(), ErrorCode> {
+        let order = self.orders.get_mut_order(id)?;
+
+        // Call the corresponding MarketHandler
+        self.handler.on_execute_order(ExecutionEvent {
+            order,
+            price,
+            quantity,
+            aggressor: taker.map(|taker| taker.order_side),
+            counterparty: taker.map(|taker| taker.id),
+            timestamp: self.timestamp,
+        });
+
+        // Update the corresponding market price
+        let order_book = self.order_books.get_order_book(&order.symbol_id)?;
+        order_book.update_last_price(order, price);
+        order_book.update_matching_price(order, price);
+        if let Some(taker) = taker {
+            order_book.update_last_price(taker, price);
+            order_book.update_matching_price(taker, price);
+        }
+
+        // Increase the order executed quantity
+        order.execute(price, quantity);
+
+        // Reduce the order in the order book
+        self.reduce_order_recursive(id, quantity, true, true)
+    }
+
+    // Calculates the matching chain from the best level of the given side
+    fn calculate_matching_chain_single_level(&mut self, symbol_id: u64, level_type: LevelType, price: u64, volume: u64) -> Result<u64, ErrorCode> {
+        let order_book = self.order_books.get_order_book(&symbol_id)?;
+        let mut available = 0;
+
+        // Travel through price levels
+        for level in order_book.levels(level_type).iter() {
+            // Check the arbitrage bid/ask prices
+            let arbitrage = if level.is_bid() {
+                price <= level.price
+            } else {
+                price >= level.price
+            };
+            if !arbitrage {
+                return Ok(0);
+            }
+
+            // Travel through orders at current price levels
+            for handle in level.queued_orders(self.orders.pool()) {
+                let order = self.orders.get_order_at(handle)?;
+                let need = volume - available;
+
+                // Skip 'All-Or-None' orders that cannot be filled completely
+                if order.is_aon() && order.leaves_quantity > need {
+                    continue;
+                }
+                available += Self::chain_quantity(order, need);
+
+                // Matching is possible, return the chain size
+                if volume == available {
+                    return Ok(available);
+                }
+            }
+        }
+
+        // Matching is not available
+        Ok(0)
+    }
+
+    // Calculates the matching chain between the best bid and the best ask levels
+    fn calculate_matching_chain_cross_levels(&mut self, symbol_id: u64) -> Result<u64, ErrorCode> {
+        let order_book = self.order_books.get_order_book(&symbol_id)?;
+
+        // Tradable quantity and 'All-Or-None' flag of every order in the level
+        let orders = &self.orders;
+        let chain = |levels: &PriceLevels, handle: LevelHandle| -> Result<std::vec::IntoIter<(u64, bool)>, ErrorCode> {
+            let Some(level) = levels.get(handle) else {
+                return Ok(Vec::new().into_iter());
+            };
+            level
+                .queued_orders(orders.pool())
+                .map(|handle| orders.get_order_at(handle).map(|order| (Self::chain_quantity(order, u64::MAX), order.is_aon())))
+                .collect::<Result<Vec<_>, ErrorCode>>()
+                .map(Vec::into_iter)
+        };
+
+        let (Some(bid_level), Some(ask_level)) = (order_book.bids.best(), order_book.asks.best()) else {
+            return Ok(0);
+        };
+
+        let mut longest_levels = &order_book.bids;
+        let mut shortest_levels = &order_book.asks;
+        let mut longest_orders = chain(longest_levels, bid_level)?;
+        let mut shortest_orders = chain(shortest_levels, ask_level)?;
+        let mut longest_level = Some(bid_level);
+        let mut shortest_level = Some(ask_level);
+        let mut longest_order = longest_orders.next();
+        let mut shortest_order = shortest_orders.next();
+
+        let (Some((longest_quantity, longest_aon)), Some((shortest_quantity, shortest_aon))) = (longest_order, shortest_order) else {
+            return Ok(0);
+        };
+
+        // Find the longest order chain
+        let mut required = longest_quantity;
+        let mut available = 0;
+        if shortest_aon && (!longest_aon || shortest_quantity > longest_quantity) {
+            required = shortest_quantity;
+            std::mem::swap(&mut longest_levels, &mut shortest_levels);
+            std::mem::swap(&mut longest_level, &mut shortest_level);
+            std::mem::swap(&mut longest_orders, &mut shortest_orders);
+            std::mem::swap(&mut longest_order, &mut shortest_order);
+        }
+
+        // Travel through price levels
+        while longest_level.is_some() && shortest_level.is_some() {
+            while let (Some(_), Some((tradable, aon))) = (longest_order, shortest_order) {
+                let need = required.saturating_sub(available);
+                let quantity = if aon {
+                    tradable
+                } else {
+                    std::cmp::min(tradable, need)
+                };
+                available += quantity;
+
+                // Matching is possible, return the chain size
+                if required == available {
+                    return Ok(required);
+                }
+
+                // Swap longest and shortest chains
+                if required < available {
+                    std::mem::swap(&mut longest_levels, &mut shortest_levels);
+                    std::mem::swap(&mut longest_level, &mut shortest_level);
+                    std::mem::swap(&mut longest_orders, &mut shortest_orders);
+                    longest_order = shortest_order;
+                    shortest_order = shortest_orders.next();
+                    std::mem::swap(&mut required, &mut available);
+                    continue;
+                }
+
+                // Take the next order
+                shortest_order = shortest_orders.next();
+            }
+
+            // Switch to the next price level, chains may have been swapped above
+            let (Some(longest_handle), Some(shortest_handle)) = (longest_level, shortest_level) else {
+                break;
+            };
+            if longest_order.is_none() {
+                longest_level = longest_levels.next(longest_handle);
+                if let Some(level) = longest_level {
+                    longest_orders = chain(longest_levels, level)?;
+                    longest_order = longest_orders.next();
+                }
+            }
+            if shortest_order.is_none() {
+                shortest_level = shortest_levels.next(shortest_handle);
+                if let Some(level) = shortest_level {
+                    shortest_orders = chain(shortest_levels, level)?;
+                    shortest_order = shortest_orders.next();
+                }
+            }
+        }
+
+        // Matching is not available
+        Ok(0)
+    }
+
+    // Executes the matching chain from the best level of the given side and returns its
+    // executed value, the sum of price times quantity
+    fn execute_matching_chain(&mut self, symbol_id: u64, level_type: LevelType, price: u64, mut volume: u64, taker: Option<&Order>) -> Result<u128, ErrorCode> {
+        let mut value = 0;
+
+        // Halted, paused and quotation only books are never crossed
+        if !self.order_books.get_order_book(&symbol_id)?.is_trading() {
+            return Ok(value);
+        }
+
+        // Execute all orders in the matching chain
+        let mut level = self.order_books.get_order_book(&symbol_id)?.levels(level_type).best();
+        while volume > 0 {
+            let Some(current) = level.take() else {
+                break;
+            };
+
+            // Find the next level before the current one is emptied by executions
+            let order_book = self.order_books.get_order_book(&symbol_id)?;
+            let levels = order_book.levels(level_type);
+            level = levels.next(current);
+
+            let Some(current_level) = levels.get(current) else {
+                break;
+            };
+
+            // Resting orders hit by a taker keep their own level price
+            let price = if taker.is_some() { current_level.price } else { price };
+
+            // Replenished iceberg slices are queued again at the back of the level,
+            // so the level is traveled until none of its orders were requeued
+            let mut requeued = true;
+            while requeued && volume > 0 {
+                requeued = false;
+
+                // The level is gone once all of its orders were executed
+                let order_book = self.order_books.get_order_book(&symbol_id)?;
+                let Some(current_level) = order_book.levels(level_type).get(current) else {
+                    break;
+                };
+                let executing = Self::queued_ids(&self.orders, current_level)?;
+
+                // Execute all orders in the current price level
+                for id in executing {
+                    if volume == 0 {
+                        break;
+                    }
+
+                    // Skip 'All-Or-None' orders left out of the chain
+                    let order = self.orders.get_order(id)?;
+                    if order.is_aon() && order.leaves_quantity > volume {
+                        continue;
+                    }
+
+                    // Execute the order quantity, only the displayed slice can be hit
+                    let quantity = Self::executable_quantity(order, volume);
+                    self.execute_order_recursive(id, price, quantity, taker)?;
+
+                    // Reduce the execution chain
+                    volume -= quantity;
+                    value += price as u128 * quantity as u128;
+
+                    // The executed order is still resting only if its slice was replenished
+                    requeued |= self.orders.contains_key(&id);
+                }
+            }
+        }
+
+        Ok(value)
+    }
+
+    // Activates stop orders triggered by the last trade prices. Activated orders are
+    // matched at once and their trades may trigger further stop orders in cascade.
+    fn activate_stop_orders(&mut self, symbol_id: u64) -> Result<bool, ErrorCode> {
+        let mut result = false;
+
+        // Stop orders are not triggered while the book is not trading
+        if !self.order_books.get_order_book(&symbol_id)?.is_trading() {
+            return Ok(false);
+        }
+
+        // Keep activating stop orders until no more can be activated
+        let mut stop = false;
+        while !stop {
+            stop = true;
+
+            // Try to activate buy stop orders traded through at or above their stop price
+            let trade_price = self.order_books.get_order_book(&symbol_id)?.get_last_trade_price_bid();
+            if self.activate_individual_stop_orders(symbol_id, OrderBook::best_buy_stop, trade_price)?
+                || self.activate_individual_stop_orders(symbol_id, OrderBook::best_trailing_buy_stop, trade_price)? {
+                result = true;
+                stop = false;
+            }
+
+            // Recalculate trailing buy stop orders
+            self.recalculate_trailing_stop_price(symbol_id, LevelType::Ask)?;
+
+            // Try to activate sell stop orders traded through at or below their stop price
+            let trade_price = self.order_books.get_order_book(&symbol_id)?.get_last_trade_price_ask();
+            if self.activate_individual_stop_orders(symbol_id, OrderBook::best_sell_stop, trade_price)?
+                || self.activate_individual_stop_orders(symbol_id, OrderBook::best_trailing_sell_stop, trade_price)? {
+                result = true;
+                stop = false;
+            }
+
+            // Recalculate trailing sell stop orders
+            self.recalculate_trailing_stop_price(symbol_id, LevelType::Bid)?;
+        }
+
+        Ok(result)
+    }
+
+    fn recalculate_trailing_stop_prices(&mut self, symbol_id: u64) -> Result<(), ErrorCode> {
+        self.recalculate_trailing_stop_price(symbol_id, LevelType::Ask)?;
+        self.recalculate_trailing_stop_price(symbol_id, LevelType::Bid)
+    }
+
+    // Moves trailing stop orders after the market price improved. Trailing buy stops
+    // follow a falling ask price and trailing sell stops follow a rising bid price.
+    fn recalculate_trailing_stop_price(&mut self, symbol_id: u64, level_type: LevelType) -> Result<(), ErrorCode> {
+        let order_book = self.order_books.get_order_book(&symbol_id)?;
+
+        // Skip recalculation if market price goes in the wrong direction
+        let levels = match level_type {
+            LevelType::Ask => {
+                let new_price = order_book.get_market_trailing_stop_price_ask();
+                if new_price >= order_book.trailing_ask_price {
+                    return Ok(());
+                }
+                order_book.trailing_ask_price = new_price;
+                &order_book.trailing_buy_stop
+            },
+            LevelType::Bid => {
+                let new_price = order_book.get_market_trailing_stop_price_bid();
+                if new_price <= order_book.trailing_bid_price {
+                    return Ok(());
+                }
+                order_book.trailing_bid_price = new_price;
+                &order_book.trailing_sell_stop
+            },
+        };
+
+        // Collect trailing stop orders first, as moved orders change their levels
+        let trailing: Vec<OrderHandle> = levels
+            .iter()
+            .flat_map(|level| level.queued_orders(self.orders.pool()))
+            .collect();
+
+        for handle in trailing {
+            let order = self.orders.get_mut_order_at(handle)?;
+            let order_book = self.order_books.get_order_book(&symbol_id)?;
+
+            let old_stop_price = order.stop_price;
+            let new_stop_price = order_book.calculate_trailing_stop_price(order)?;
+            if new_stop_price == old_stop_price {
+                continue;
+            }
+
+            // Move the order to the trailing stop level of its new stop price
+            order_book.delete_trailing_stop_order(self.orders.pool_mut(), handle)?;
+            let order = self.orders.get_mut_order_at(handle)?;
+            if order.is_trailing_stop_limit() {
+                // The limit price keeps its distance to the stop price
+                order.price = if new_stop_price > old_stop_price {
+                    order.price.saturating_add(new_stop_price - old_stop_price)
+                } else {
+                    order.price.saturating_sub(old_stop_price - new_stop_price)
+                };
+            }
+            order.stop_price = new_stop_price;
+            order_book.add_trailing_stop_order(self.orders.pool_mut(), handle)?;
+
+            // Call the corresponding MarketHandler
+            self.handler.on_update_order(OrderEvent { order: self.orders.get_order_at(handle)?, timestamp: self.timestamp });
+        }
+
+        Ok(())
+    }
+
+    // Activates the orders of the best stop level selected from the order book once the
+    // stop price crosses it
+    fn activate_individual_stop_orders(&mut self, symbol_id: u64, best_level: fn(&OrderBook) -> Option<&Level>, stop_price: u64) -> Result<bool, ErrorCode> {
+        let order_book = self.order_books.get_order_book(&symbol_id)?;
+        let Some(level) = best_level(order_book) else {
+            return Ok(false);
+        };
+
+        // Check the arbitrage bid/ask prices
+        let arbitrage = if level.is_bid() {
+            stop_price <= level.price
+        } else {
+            stop_price >= level.price
+        };
+        if !arbitrage {
+            return Ok(false);
+        }
+
+        let activating = Self::queued_ids(&self.orders, level)?;
+
+        // Activate all stop orders
+        let mut result = false;
+        for id in activating {
+            match self.orders.get_order(id)?.order_type {
+                OrderType::Stop | OrderType::TrailingStop => {
+                    result |= self.activate_stop_order(symbol_id, id)?;
+                },
+                OrderType::StopLimit | OrderType::TrailingStopLimit => {
+                    result |= self.activate_stop_limit_order(symbol_id, id)?;
+                },
+                _ => return Err(ErrorCode::OrderTypeInvalid),
+            }
+        }
+
+        Ok(result)
+    }
+
+    fn activate_stop_order(&mut self, symbol_id: u64, id: u64) -> Result<bool, ErrorCode> {
+        let handle = self.orders.get_handle(id)?;
+        let order = self.orders.get_order_at(handle)?;
+        let trailing = order.is_trailing_stop() || order.is_trailing_stop_limit();
+        let order_book = self.order_books.get_order_book(&symbol_id)?;
+
+        // Delete the stop order from the order book
+        if trailing {
+            order_book.delete_trailing_stop_order(self.orders.pool_mut(), handle)?;
+        } else {
+            order_book.delete_stop_order(self.orders.pool_mut(), handle)?;
+        }
+        let mut order = self.orders.remove_order(&id).ok_or(ErrorCode::OrderNotFound)?;
+
+        // Convert the stop order into the market order
+        order.order_type = OrderType::Market;
+        order.price = 0;
+        order.stop_price = 0;
+        order.time_in_force = if order.is_fok() { TimeInForce::FOK } else { TimeInForce::IOC };
+
+        // Call the corresponding MarketHandler
+        self.handler.on_update_order(OrderEvent { order: &order, timestamp: self.timestamp });
+
+        // Match the market order
+        self.match_market(&mut order)?;
+
+        // Cancel the remaining part of the market order
+        if order.leaves_quantity > 0 {
+            // Call the corresponding MarketHandler
+            self.handler.on_delete_unmatched_order(OrderEvent { order: &order, timestamp: self.timestamp });
+        } else {
+            // Call the corresponding MarketHandler
+            self.handler.on_delete_order(OrderEvent { order: &order, timestamp: self.timestamp });
+        }
+
+        Ok(true)
+    }
+
+    fn activate_stop_limit_order(&mut self, symbol_id: u64, id: u64) -> Result<bool, ErrorCode> {
+        let handle = self.orders.get_handle(id)?;
+        let order = self.orders.get_order_at(handle)?;
+        let trailing = order.is_trailing_stop() || order.is_trailing_stop_limit();
+        let order_book = self.order_books.get_order_book(&symbol_id)?;
+
+        // Keep the stop order if its limit price cannot rest in the order book
+        order_book.validate_price(order.price)?;
+
+        // Delete the stop order from the order book
+        if trailing {
+            order_book.delete_trailing_stop_order(self.orders.pool_mut(), handle)?;
+        } else {
+            order_book.delete_stop_order(self.orders.pool_mut(), handle)?;
+        }
+        let mut order = self.orders.remove_order(&id).ok_or(ErrorCode::OrderNotFound)?;
+
+        // Convert the stop-limit order into the limit order
+        order.order_type = OrderType::Limit;
+        order.stop_price = 0;
+
+        // Call the corresponding MarketHandler
+        self.handler.on_update_order(OrderEvent { order: &order, timestamp: self.timestamp });
+
+        // Match the limit order
+        self.match_limit(&mut order)?;
+
+        // Add a new limit order or delete remaining part in case of 'Immediate-Or-Cancel'/'Fill-Or-Kill' order
+        if order.leaves_quantity > 0 && !order.is_ioc() && !order.is_fok() {
+            let handle = self.orders.insert_order(&order)?;
+            let order_book = self.order_books.get_order_book(&symbol_id)?;
+            let update = order_book.add_order(self.orders.pool_mut(), handle)?;
+            Self::update_level(&mut self.handler, self.timestamp, order_book, update);
+            self.schedule_expiry(&order);
+        } else if order.leaves_quantity > 0 {
+            // Call the corresponding MarketHandler
+            self.handler.on_delete_unmatched_order(OrderEvent { order: &order, timestamp: self.timestamp });
+        } else {
+            // Call the corresponding MarketHandler
+            self.handler.on_delete_order(OrderEvent { order: &order, timestamp: self.timestamp });
+        }
+
+        Ok(true)
+    }
+}
@@ -1,3 +1,2 @@
-pub mod executor;
-pub mod execution;
-pub mod order_book_operations;
\ No newline at end of file
+pub mod market_manager;
+pub mod order_book_operations;
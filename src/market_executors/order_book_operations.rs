@@ -1,8 +1,8 @@
 
-use std::collections::HashMap;
+use std::collections::{hash_map::Entry, HashMap};
 use std::ops::{Deref, DerefMut};
 
-use crate::order_book::order_book::OrderBook;
+use crate::order_book::{order_book::OrderBook, reference_data::{CircuitBreaker, SymbolReference}};
 use crate::orders::order::ErrorCode;
 
 
@@ -16,6 +16,22 @@ pub trait OrderBookContainer{
 pub struct OBMap
 {
     books: HashMap<u64, OrderBook>,
+    // Market-wide state shared by all symbols
+    circuit_breaker: CircuitBreaker,
+}
+
+impl OBMap {
+    pub fn reference_data(&self, symbol: &u64) -> Result<&SymbolReference, ErrorCode> {
+        self.books.get(symbol).map(|order_book| &order_book.reference).ok_or(ErrorCode::OrderBookNotFound)
+    }
+
+    pub fn circuit_breaker(&self) -> &CircuitBreaker {
+        &self.circuit_breaker
+    }
+
+    pub fn circuit_breaker_mut(&mut self) -> &mut CircuitBreaker {
+        &mut self.circuit_breaker
+    }
 }
 
 impl Deref for OBMap {
@@ -36,11 +52,12 @@ impl DerefMut for OBMap {
 impl OrderBookContainer for OBMap
 {
     fn add_order_book(&mut self, symbol: u64, order_book: OrderBook) -> Result<(), ErrorCode> {
-        if self.contains_key(&symbol) {
-            Err(ErrorCode::OrderBookDuplicate)
-        } else {
-            self.insert(symbol, order_book);
-            Ok(())
+        match self.entry(symbol) {
+            Entry::Occupied(_) => Err(ErrorCode::OrderBookDuplicate),
+            Entry::Vacant(entry) => {
+                entry.insert(order_book);
+                Ok(())
+            },
         }
     }
 
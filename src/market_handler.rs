@@ -1,34 +1,67 @@
+use std::collections::HashMap;
 
+use crate::{levels::level::Level, order_book::{imbalance::Imbalance, order_book::OrderBook, reference_data::{CircuitBreaker, ReferenceUpdate}, trading_state::TradingState}, orders::order::{ErrorCode, Order, OrderSide}, symbols::symbol::Symbol, trades::trade_tape::Trade};
 
+// Order added, updated or deleted at the given feed timestamp
+#[derive(Clone, Copy, Debug)]
+pub struct OrderEvent<'a> {
+    pub order: &'a Order,
+    pub timestamp: u64,
+}
 
-use crate::{levels::level::Level, order_book::order_book::OrderBook, orders::order::Order};
+// Order executed for the given price and quantity. Aggressor side and counterparty
+// order id are unknown when crossed order books are matched without an incoming order.
+#[derive(Clone, Copy, Debug)]
+pub struct ExecutionEvent<'a> {
+    pub order: &'a Order,
+    pub price: u64,
+    pub quantity: u64,
+    pub aggressor: Option<OrderSide>,
+    pub counterparty: Option<u64>,
+    pub timestamp: u64,
+}
 
-pub trait Handler                                           
-{
-    fn new(
-        max_symbols: u64,
-        max_order_books: u64,
-        max_order_book_levels: u64,
-        max_order_book_orders: u64,
-        max_orders: u64
-    ) -> Self;
-    fn on_execute_order(order: &Order, price: u64, leaves_quantity: u64);
-    fn on_add_level(order_book: &OrderBook, level: &Level, top: bool);
-    fn on_update_level(order_book: &OrderBook, level: &Level, top: bool);
-    fn on_delete_level(order_book: &OrderBook, level: &Level, top: bool);
-    fn on_update_order_book(order_book: &OrderBook,  top: bool);
-    fn on_delete_order(order: &Order);
-    fn on_update_order(order: &Order);
-    fn on_delete_unmatched_order( order: &Order);
-    fn on_add_order(order: &Order);
-    fn on_delete_order_book(order_book: &OrderBook);
-    fn on_add_order_book(order_book: &OrderBook);
-    fn delete_stop_order(order: &Order);
+// Price level added, updated or deleted in the order book
+#[derive(Clone, Copy)]
+pub struct LevelEvent<'a> {
+    pub order_book: &'a OrderBook,
+    pub level: &'a Level,
+    pub top: bool,
+    pub timestamp: u64,
 }
 
-//impl Handler for MarketHandler {}
+pub trait Handler
+{
+    fn on_add_symbol(&mut self, symbol: &Symbol);
+    fn on_delete_symbol(&mut self, symbol: &Symbol);
+    fn on_add_order_book(&mut self, order_book: &OrderBook);
+    fn on_update_order_book(&mut self, order_book: &OrderBook, top: bool);
+    fn on_delete_order_book(&mut self, order_book: &OrderBook);
+    fn on_add_level(&mut self, event: LevelEvent);
+    fn on_update_level(&mut self, event: LevelEvent);
+    fn on_delete_level(&mut self, event: LevelEvent);
+    fn on_add_order(&mut self, event: OrderEvent);
+    fn on_update_order(&mut self, event: OrderEvent);
+    fn on_delete_order(&mut self, event: OrderEvent);
+    fn on_delete_unmatched_order(&mut self, event: OrderEvent);
+    fn on_execute_order(&mut self, event: ExecutionEvent);
+    fn on_trading_status_change(&mut self, order_book: &OrderBook, previous: TradingState);
+    // The order book already holds the new imbalance, the previous one is passed along
+    fn on_imbalance(&mut self, order_book: &OrderBook, previous: Option<Imbalance>);
+    // The order book statistics already exclude the broken trade
+    fn on_broken_trade(&mut self, order_book: &OrderBook, trade: &Trade);
+    fn on_reference_data(&mut self, order_book: &OrderBook, update: ReferenceUpdate);
+    fn on_circuit_breaker(&mut self, circuit_breaker: &CircuitBreaker);
+    fn on_gap_open(&mut self, from: u64, to: u64);
+    // The gap was either filled by recovered messages or abandoned
+    fn on_gap_close(&mut self, from: u64, to: u64, recovered: bool);
+    // The replayed ITCH message could not be applied and was skipped
+    fn on_message_error(&mut self, message: &[u8], error: &ErrorCode);
+}
 
-#[derive(Clone)]
+// Market handler collecting feed statistics. The max_* fields are high-water marks
+// of the corresponding live counters.
+#[derive(Clone, Default)]
 pub struct MarketHandler {
     updates: u64,
     symbols: u64,
@@ -43,105 +76,208 @@ pub struct MarketHandler {
     update_orders: u64,
     delete_orders: u64,
     execute_orders: u64,
+    // Feed gaps opened, recovered and abandoned by the sequencer
+    gaps: u64,
+    recovered_gaps: u64,
+    abandoned_gaps: u64,
+    // Replayed ITCH messages skipped because they could not be applied
+    message_errors: u64,
+    // Live levels and orders per order book, keyed by symbol id
+    order_book_levels: HashMap<u64, u64>,
+    order_book_orders: HashMap<u64, u64>,
 }
 
-impl Default for MarketHandler {
-    fn default() -> Self {
-        MarketHandler {
-            updates: 0,
-            symbols: 0,
-            max_symbols: 1000, // You can set this to a sensible default value
-            order_books: 0,
-            max_order_books: 1000, // You can set this to a sensible default value
-            max_order_book_levels: 100, // You can set this to a sensible default value
-            max_order_book_orders: 1000, // You can set this to a sensible default value
-            orders: 0,
-            max_orders: 10000, // You can set this to a sensible default value
-            add_orders: 0,
-            update_orders: 0,
-            delete_orders: 0,
-            execute_orders: 0,
+impl MarketHandler {
+    pub fn new() -> Self {
+        MarketHandler::default()
+    }
+
+    pub fn updates(&self) -> u64 {
+        self.updates
+    }
+
+    pub fn symbols(&self) -> u64 {
+        self.symbols
+    }
+
+    pub fn max_symbols(&self) -> u64 {
+        self.max_symbols
+    }
+
+    pub fn order_books(&self) -> u64 {
+        self.order_books
+    }
+
+    pub fn max_order_books(&self) -> u64 {
+        self.max_order_books
+    }
+
+    pub fn max_order_book_levels(&self) -> u64 {
+        self.max_order_book_levels
+    }
+
+    pub fn max_order_book_orders(&self) -> u64 {
+        self.max_order_book_orders
+    }
+
+    pub fn orders(&self) -> u64 {
+        self.orders
+    }
+
+    pub fn max_orders(&self) -> u64 {
+        self.max_orders
+    }
+
+    pub fn add_orders(&self) -> u64 {
+        self.add_orders
+    }
+
+    pub fn update_orders(&self) -> u64 {
+        self.update_orders
+    }
+
+    pub fn delete_orders(&self) -> u64 {
+        self.delete_orders
+    }
+
+    pub fn execute_orders(&self) -> u64 {
+        self.execute_orders
+    }
+
+    pub fn gaps(&self) -> u64 {
+        self.gaps
+    }
+
+    pub fn recovered_gaps(&self) -> u64 {
+        self.recovered_gaps
+    }
+
+    pub fn abandoned_gaps(&self) -> u64 {
+        self.abandoned_gaps
+    }
+
+    pub fn message_errors(&self) -> u64 {
+        self.message_errors
+    }
+
+    fn remove_order(&mut self, order: &Order) {
+        self.updates += 1;
+        self.orders = self.orders.saturating_sub(1);
+        self.delete_orders += 1;
+        if let Some(orders) = self.order_book_orders.get_mut(&order.symbol_id) {
+            *orders = orders.saturating_sub(1);
         }
     }
 }
 
 impl Handler for MarketHandler {
-    fn new(
-        max_symbols: u64,
-        max_order_books: u64,
-        max_order_book_levels: u64,
-        max_order_book_orders: u64,
-        max_orders: u64
-    ) -> Self {
-        MarketHandler {
-            updates: 0,
-            symbols: 0,
-            max_symbols,
-            order_books: 0,
-            max_order_books,
-            max_order_book_levels,
-            max_order_book_orders,
-            orders: 0,
-            max_orders,
-            add_orders: 0,
-            update_orders: 0,
-            delete_orders: 0,
-            execute_orders: 0,
+    fn on_add_symbol(&mut self, _symbol: &Symbol) {
+        self.updates += 1;
+        self.symbols += 1;
+        self.max_symbols = std::cmp::max(self.symbols, self.max_symbols);
+    }
+
+    fn on_delete_symbol(&mut self, _symbol: &Symbol) {
+        self.updates += 1;
+        self.symbols = self.symbols.saturating_sub(1);
+    }
+
+    fn on_add_order_book(&mut self, _order_book: &OrderBook) {
+        self.updates += 1;
+        self.order_books += 1;
+        self.max_order_books = std::cmp::max(self.order_books, self.max_order_books);
+    }
+
+    fn on_update_order_book(&mut self, order_book: &OrderBook, _top: bool) {
+        let id = order_book.symbol.id;
+        let levels = self.order_book_levels.get(&id).copied().unwrap_or(0);
+        let orders = self.order_book_orders.get(&id).copied().unwrap_or(0);
+        self.max_order_book_levels = std::cmp::max(levels, self.max_order_book_levels);
+        self.max_order_book_orders = std::cmp::max(orders, self.max_order_book_orders);
+    }
+
+    fn on_delete_order_book(&mut self, order_book: &OrderBook) {
+        self.updates += 1;
+        self.order_books = self.order_books.saturating_sub(1);
+        self.order_book_levels.remove(&order_book.symbol.id);
+        self.order_book_orders.remove(&order_book.symbol.id);
+    }
+
+    fn on_add_level(&mut self, event: LevelEvent) {
+        self.updates += 1;
+        *self.order_book_levels.entry(event.order_book.symbol.id).or_insert(0) += 1;
+    }
+
+    fn on_update_level(&mut self, _event: LevelEvent) {
+        self.updates += 1;
+    }
+
+    fn on_delete_level(&mut self, event: LevelEvent) {
+        self.updates += 1;
+        if let Some(levels) = self.order_book_levels.get_mut(&event.order_book.symbol.id) {
+            *levels = levels.saturating_sub(1);
         }
     }
 
-    fn delete_stop_order(order: &Order) {}
-    
-    fn on_add_order(order: &Order) {}
-    //     self.updates += 1;
-    //     self.orders += 1;
-    //     self.max_orders = std::cmp::max(self.orders, self.max_orders);
-    //     self.add_orders += 1;
-    // }
-    
-    fn on_delete_order(order: &Order) {
-        println!("Deleted order: {:?}", order);
+    fn on_add_order(&mut self, event: OrderEvent) {
+        self.updates += 1;
+        self.orders += 1;
+        self.max_orders = std::cmp::max(self.orders, self.max_orders);
+        self.add_orders += 1;
+        *self.order_book_orders.entry(event.order.symbol_id).or_insert(0) += 1;
+    }
+
+    fn on_update_order(&mut self, _event: OrderEvent) {
+        self.updates += 1;
+        self.update_orders += 1;
     }
 
-    fn on_delete_unmatched_order(order: &Order) {
-        println!("Deleted order: {:?}", order);
+    fn on_delete_order(&mut self, event: OrderEvent) {
+        self.remove_order(event.order);
     }
 
-    fn on_delete_order_book(order_book: &OrderBook) {
-       // println!("Deleted order book: {:?}", order_book);
+    fn on_delete_unmatched_order(&mut self, event: OrderEvent) {
+        self.remove_order(event.order);
     }
 
-    fn on_update_order(order: &Order) {
-        println!("Order Updated: {:?}", order);
+    fn on_execute_order(&mut self, _event: ExecutionEvent) {
+        self.updates += 1;
+        self.execute_orders += 1;
     }
 
-    fn on_execute_order(order: &Order, quantity: u64, price: u64) {
-        println!("Executed order: {:?}, Quantity: {}, Price: {}", order, quantity, price);
+    fn on_trading_status_change(&mut self, _order_book: &OrderBook, _previous: TradingState) {
+        self.updates += 1;
     }
 
-    fn on_add_order_book(order_book: &OrderBook) {
-      //  println!("Added order book: {:?}", order_book);
-        // Implement specific logic for MarketHandler when an order book is added
+    fn on_imbalance(&mut self, _order_book: &OrderBook, _previous: Option<Imbalance>) {
+        self.updates += 1;
     }
-    fn on_update_level(order_book: &OrderBook, level: &Level, top: bool) {
-     //   println!("Updated level in order book: {:?}, Level: {:?}op: {}", order_book, level, top);
-        // Additional logic for updating a level...
+
+    fn on_broken_trade(&mut self, _order_book: &OrderBook, _trade: &Trade) {
+        self.updates += 1;
+    }
+
+    fn on_reference_data(&mut self, _order_book: &OrderBook, _update: ReferenceUpdate) {
+        self.updates += 1;
+    }
+
+    fn on_circuit_breaker(&mut self, _circuit_breaker: &CircuitBreaker) {
+        self.updates += 1;
     }
 
-    fn on_delete_level(order_book: &OrderBook, level: &Level, top: bool) 
-    {
-       // println!("Deleted level from order book: {:?}, Level: {:?}op: {}", order_book, level, top);
-        // Additional logic for deleting a level...
+    fn on_gap_open(&mut self, _from: u64, _to: u64) {
+        self.gaps += 1;
     }
 
-    fn on_add_level(order_book: &OrderBook, level: &Level, top: bool) 
-    {
-      //  println!("Added level to order book: {:?}, Level: {:?}op: {}", order_book, level, top);
-        // Additional logic for adding a level...
+    fn on_gap_close(&mut self, _from: u64, _to: u64, recovered: bool) {
+        if recovered {
+            self.recovered_gaps += 1;
+        } else {
+            self.abandoned_gaps += 1;
+        }
     }
-    fn on_update_order_book(order_book: &OrderBook, top: bool) 
-    {
-      //  println!("Updated order book: {:?}op: {}", order_book, top);
-        // Additional logic for updating the order book...
+
+    fn on_message_error(&mut self, _message: &[u8], _error: &ErrorCode) {
+        self.message_errors += 1;
     }
-}
\ No newline at end of file
+}
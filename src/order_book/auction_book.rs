@@ -0,0 +1,46 @@
+use std::collections::VecDeque;
+
+use crate::orders::order::ErrorCode;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AuctionType {
+    Opening,
+    Closing,
+}
+
+// Orders waiting for the opening or closing cross. They are kept in time priority
+// and take no part in continuous matching until the auction is crossed.
+#[derive(Default)]
+pub struct AuctionBook {
+    orders: VecDeque<u64>,
+}
+
+impl AuctionBook {
+    pub fn is_empty(&self) -> bool {
+        self.orders.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.orders.len()
+    }
+
+    // Order ids in time priority
+    pub fn orders(&self) -> impl Iterator<Item = &u64> {
+        self.orders.iter()
+    }
+
+    pub fn add_order(&mut self, id: u64) {
+        self.orders.push_back(id);
+    }
+
+    pub fn delete_order(&mut self, id: u64) -> Result<(), ErrorCode> {
+        let position = self.orders.iter().position(|order| *order == id).ok_or(ErrorCode::OrderNotFound)?;
+        self.orders.remove(position);
+        Ok(())
+    }
+
+    // Removes all orders so they can be released into the cross
+    pub fn take_orders(&mut self) -> Vec<u64> {
+        self.orders.drain(..).collect()
+    }
+}
@@ -0,0 +1,117 @@
+use crate::itch_handler::NOIIView;
+
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub enum ImbalanceDirection {
+    Buy,
+    Sell,
+    NoImbalance,
+    InsufficientOrders,
+    Paused,
+    #[default]
+    NotAvailable,
+}
+
+impl From<u8> for ImbalanceDirection {
+    fn from(code: u8) -> Self {
+        match code {
+            b'B' => ImbalanceDirection::Buy,
+            b'S' => ImbalanceDirection::Sell,
+            b'N' => ImbalanceDirection::NoImbalance,
+            b'O' => ImbalanceDirection::InsufficientOrders,
+            b'P' => ImbalanceDirection::Paused,
+            _ => ImbalanceDirection::NotAvailable,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub enum CrossType {
+    Opening,
+    Closing,
+    // IPO and halted securities
+    Halt,
+    // Intraday and post-close crosses
+    Intraday,
+    ExtendedTradingClose,
+    #[default]
+    NotAvailable,
+}
+
+impl From<u8> for CrossType {
+    fn from(code: u8) -> Self {
+        match code {
+            b'O' => CrossType::Opening,
+            b'C' => CrossType::Closing,
+            b'H' => CrossType::Halt,
+            b'I' => CrossType::Intraday,
+            b'A' => CrossType::ExtendedTradingClose,
+            _ => CrossType::NotAvailable,
+        }
+    }
+}
+
+// Deviation of the near price from the current reference price
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub enum PriceVariation {
+    LessThanOnePercent,
+    // Lower bound of the deviation bucket in percent: 1 to 9, 10, 20 or 30 and above
+    Percent(u8),
+    #[default]
+    NotAvailable,
+}
+
+impl From<u8> for PriceVariation {
+    fn from(code: u8) -> Self {
+        match code {
+            b'L' => PriceVariation::LessThanOnePercent,
+            b'1'..=b'9' => PriceVariation::Percent(code - b'0'),
+            b'A' => PriceVariation::Percent(10),
+            b'B' => PriceVariation::Percent(20),
+            b'C' => PriceVariation::Percent(30),
+            _ => PriceVariation::NotAvailable,
+        }
+    }
+}
+
+// Latest Net Order Imbalance Indicator ('I') published for a symbol ahead of its cross
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Imbalance {
+    pub paired_shares: u64,
+    pub imbalance_shares: u64,
+    pub imbalance_direction: ImbalanceDirection,
+    pub far_price: u64,
+    pub near_price: u64,
+    pub current_reference_price: u64,
+    pub cross_type: CrossType,
+    pub price_variation: PriceVariation,
+    pub timestamp: u64,
+}
+
+impl Imbalance {
+    // Price the cross would currently happen at, if there is one yet
+    pub fn indicative_price(&self) -> Option<u64> {
+        if self.near_price > 0 {
+            Some(self.near_price)
+        } else if self.current_reference_price > 0 {
+            Some(self.current_reference_price)
+        } else {
+            None
+        }
+    }
+}
+
+impl From<&NOIIView<'_>> for Imbalance {
+    fn from(message: &NOIIView<'_>) -> Self {
+        Imbalance {
+            paired_shares: message.paired_shares(),
+            imbalance_shares: message.imbalance_shares(),
+            imbalance_direction: ImbalanceDirection::from(message.imbalance_direction()),
+            far_price: message.far_price() as u64,
+            near_price: message.near_price() as u64,
+            current_reference_price: message.current_reference_price() as u64,
+            cross_type: CrossType::from(message.cross_type()),
+            price_variation: PriceVariation::from(message.price_variation_indicator()),
+            timestamp: message.timestamp(),
+        }
+    }
+}
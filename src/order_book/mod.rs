@@ -1,2 +1,8 @@
-
+pub mod auction_book;
+pub mod imbalance;
+#[allow(clippy::module_inception)]
 pub mod order_book;
+pub mod participant_depth;
+pub mod reference_data;
+pub mod trade_statistics;
+pub mod trading_state;
@@ -0,0 +1,65 @@
+use std::collections::BTreeMap;
+
+use crate::orders::order::OrderSide;
+
+// Market participant identifier attributed by Add Order With MPID ('F') messages
+pub type Mpid = [u8; 4];
+
+// Displayed liquidity of one market participant aggregated by price level
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ParticipantDepth {
+    bids: BTreeMap<u64, u64>,
+    asks: BTreeMap<u64, u64>,
+}
+
+impl ParticipantDepth {
+    pub fn is_empty(&self) -> bool {
+        self.bids.is_empty() && self.asks.is_empty()
+    }
+
+    // Displayed volume of the participant at the given price
+    pub fn volume(&self, side: OrderSide, price: u64) -> u64 {
+        self.levels(side).get(&price).copied().unwrap_or(0)
+    }
+
+    // Price and displayed volume of every bid level, best first
+    pub fn bids(&self) -> impl Iterator<Item = (u64, u64)> + '_ {
+        self.bids.iter().rev().map(|(price, volume)| (*price, *volume))
+    }
+
+    // Price and displayed volume of every ask level, best first
+    pub fn asks(&self) -> impl Iterator<Item = (u64, u64)> + '_ {
+        self.asks.iter().map(|(price, volume)| (*price, *volume))
+    }
+
+    pub fn add_volume(&mut self, side: OrderSide, price: u64, volume: u64) {
+        if volume > 0 {
+            *self.levels_mut(side).entry(price).or_insert(0) += volume;
+        }
+    }
+
+    // Levels are dropped once the participant shows nothing at them anymore
+    pub fn subtract_volume(&mut self, side: OrderSide, price: u64, volume: u64) {
+        let levels = self.levels_mut(side);
+        if let Some(displayed) = levels.get_mut(&price) {
+            *displayed = displayed.saturating_sub(volume);
+            if *displayed == 0 {
+                levels.remove(&price);
+            }
+        }
+    }
+
+    fn levels(&self, side: OrderSide) -> &BTreeMap<u64, u64> {
+        match side {
+            OrderSide::Buy => &self.bids,
+            OrderSide::Sell => &self.asks,
+        }
+    }
+
+    fn levels_mut(&mut self, side: OrderSide) -> &mut BTreeMap<u64, u64> {
+        match side {
+            OrderSide::Buy => &mut self.bids,
+            OrderSide::Sell => &mut self.asks,
+        }
+    }
+}
@@ -0,0 +1,176 @@
+use std::collections::HashMap;
+
+use crate::itch_handler::{DLCRPriceDiscoveryView, IPOQuotingPeriodUpdateView, LULDAuctionCollarView, MWCBDeclineView, MarketParticipantPositionView};
+
+use super::participant_depth::Mpid;
+
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub enum MarketMakerMode {
+    Normal,
+    Passive,
+    Syndicate,
+    PreSyndicate,
+    Penalty,
+    #[default]
+    NotAvailable,
+}
+
+impl From<u8> for MarketMakerMode {
+    fn from(code: u8) -> Self {
+        match code {
+            b'N' => MarketMakerMode::Normal,
+            b'P' => MarketMakerMode::Passive,
+            b'S' => MarketMakerMode::Syndicate,
+            b'R' => MarketMakerMode::PreSyndicate,
+            b'L' => MarketMakerMode::Penalty,
+            _ => MarketMakerMode::NotAvailable,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub enum ParticipantState {
+    Active,
+    Excused,
+    Withdrawn,
+    Suspended,
+    Deleted,
+    #[default]
+    NotAvailable,
+}
+
+impl From<u8> for ParticipantState {
+    fn from(code: u8) -> Self {
+        match code {
+            b'A' => ParticipantState::Active,
+            b'E' => ParticipantState::Excused,
+            b'W' => ParticipantState::Withdrawn,
+            b'S' => ParticipantState::Suspended,
+            b'D' => ParticipantState::Deleted,
+            _ => ParticipantState::NotAvailable,
+        }
+    }
+}
+
+// Registration of a market participant in a symbol ('L')
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ParticipantPosition {
+    pub primary_market_maker: bool,
+    pub market_maker_mode: MarketMakerMode,
+    pub participant_state: ParticipantState,
+}
+
+impl From<&MarketParticipantPositionView<'_>> for ParticipantPosition {
+    fn from(message: &MarketParticipantPositionView<'_>) -> Self {
+        ParticipantPosition {
+            primary_market_maker: message.primary_market_maker() == b'Y',
+            market_maker_mode: MarketMakerMode::from(message.market_maker_mode()),
+            participant_state: ParticipantState::from(message.market_participant_state()),
+        }
+    }
+}
+
+// Anticipated quotation release of an IPO ('K'). A canceled release is postponed.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct IpoQuotingPeriod {
+    // Seconds since midnight
+    pub release_time: u32,
+    pub canceled: bool,
+    pub ipo_price: u64,
+}
+
+impl From<&IPOQuotingPeriodUpdateView<'_>> for IpoQuotingPeriod {
+    fn from(message: &IPOQuotingPeriodUpdateView<'_>) -> Self {
+        IpoQuotingPeriod {
+            release_time: message.ipo_quotation_release_time(),
+            canceled: message.ipo_quotation_release_qualifier() == b'C',
+            ipo_price: message.ipo_price() as u64,
+        }
+    }
+}
+
+// Price bounds of the LULD reopening auction ('J')
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct AuctionCollar {
+    pub reference_price: u64,
+    pub upper_price: u64,
+    pub lower_price: u64,
+    // Number of extensions of the reopening auction
+    pub extension: u32,
+}
+
+impl From<&LULDAuctionCollarView<'_>> for AuctionCollar {
+    fn from(message: &LULDAuctionCollarView<'_>) -> Self {
+        AuctionCollar {
+            reference_price: message.auction_collar_reference_price() as u64,
+            upper_price: message.upper_auction_collar_price() as u64,
+            lower_price: message.lower_auction_collar_price() as u64,
+            extension: message.auction_collar_extension(),
+        }
+    }
+}
+
+// Direct Listing with Capital Raise price discovery ('O')
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PriceDiscovery {
+    pub open_eligible: bool,
+    pub minimum_allowable_price: u64,
+    pub maximum_allowable_price: u64,
+    pub near_execution_price: u64,
+    pub near_execution_time: u64,
+    pub lower_price_range_collar: u64,
+    pub upper_price_range_collar: u64,
+}
+
+impl From<&DLCRPriceDiscoveryView<'_>> for PriceDiscovery {
+    fn from(message: &DLCRPriceDiscoveryView<'_>) -> Self {
+        PriceDiscovery {
+            open_eligible: message.open_eligibility_status() == b'Y',
+            minimum_allowable_price: message.minimum_allowable_price() as u64,
+            maximum_allowable_price: message.maximum_allowable_price() as u64,
+            near_execution_price: message.near_execution_price() as u64,
+            near_execution_time: message.near_execution_time(),
+            lower_price_range_collar: message.lower_price_range_collar() as u64,
+            upper_price_range_collar: message.upper_price_range_collar() as u64,
+        }
+    }
+}
+
+// Reference state of a symbol from the messages that do not touch its orders
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SymbolReference {
+    pub participants: HashMap<Mpid, ParticipantPosition>,
+    pub ipo_quoting_period: Option<IpoQuotingPeriod>,
+    pub auction_collar: Option<AuctionCollar>,
+    pub price_discovery: Option<PriceDiscovery>,
+}
+
+// Part of the symbol reference changed by a message
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ReferenceUpdate {
+    ParticipantPosition(Mpid),
+    IpoQuotingPeriod,
+    AuctionCollar,
+    PriceDiscovery,
+}
+
+// Market-Wide Circuit Breaker decline levels ('V') and breach status ('W')
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct CircuitBreaker {
+    // Index values of the level 1, 2 and 3 declines
+    pub decline_levels: Option<[u64; 3]>,
+    // Highest level breached during the day, from 1 to 3
+    pub breached_level: Option<u8>,
+}
+
+impl CircuitBreaker {
+    pub fn update_decline_levels(&mut self, message: &MWCBDeclineView<'_>) {
+        self.decline_levels = Some([message.level1(), message.level2(), message.level3()]);
+    }
+
+    pub fn update_breached_level(&mut self, code: u8) {
+        if let b'1'..=b'3' = code {
+            self.breached_level = Some(code - b'0');
+        }
+    }
+}
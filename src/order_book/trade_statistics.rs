@@ -0,0 +1,43 @@
+use crate::trades::trade_tape::Trade;
+
+// Session statistics of the printable trades of a symbol
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TradeStatistics {
+    pub trades: u64,
+    pub volume: u64,
+    // Sum of price times quantity over all printable trades
+    pub value: u128,
+    pub last_price: Option<u64>,
+}
+
+impl TradeStatistics {
+    pub fn add_trade(&mut self, trade: &Trade) {
+        if !trade.printable {
+            return;
+        }
+        self.trades += 1;
+        self.volume += trade.quantity;
+        self.value += trade.price as u128 * trade.quantity as u128;
+        self.last_price = Some(trade.price);
+    }
+
+    // Takes a broken trade back out. The last price falls back to the given price of
+    // the latest trade still standing.
+    pub fn remove_trade(&mut self, trade: &Trade, last_price: Option<u64>) {
+        if !trade.printable {
+            return;
+        }
+        self.trades = self.trades.saturating_sub(1);
+        self.volume = self.volume.saturating_sub(trade.quantity);
+        self.value = self.value.saturating_sub(trade.price as u128 * trade.quantity as u128);
+        self.last_price = last_price;
+    }
+
+    // Volume weighted average price of the printable trades
+    pub fn vwap(&self) -> Option<u64> {
+        if self.volume == 0 {
+            return None;
+        }
+        Some((self.value / self.volume as u128) as u64)
+    }
+}
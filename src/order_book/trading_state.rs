@@ -0,0 +1,65 @@
+use crate::orders::order::ErrorCode;
+
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub enum TradingStatus {
+    Halted,
+    Paused,
+    QuotationOnly,
+    #[default]
+    Trading,
+}
+
+impl TryFrom<u8> for TradingStatus {
+    type Error = ErrorCode;
+
+    fn try_from(code: u8) -> Result<Self, Self::Error> {
+        match code {
+            b'H' => Ok(TradingStatus::Halted),
+            b'P' => Ok(TradingStatus::Paused),
+            b'Q' => Ok(TradingStatus::QuotationOnly),
+            b'T' => Ok(TradingStatus::Trading),
+            _ => Err(ErrorCode::OtherError(format!("Unknown trading state '{}'", code as char))),
+        }
+    }
+}
+
+// Combined per symbol state driven by the 'H', 'Y' and 'h' messages
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TradingState {
+    pub status: TradingStatus,
+    pub reg_sho_restricted: bool,
+    pub operational_halt: bool,
+}
+
+impl TradingState {
+    // Continuous matching is only allowed in the trading state and outside of operational halts
+    pub fn is_trading(&self) -> bool {
+        self.status == TradingStatus::Trading && !self.operational_halt
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SystemEvent {
+    StartOfMessages,
+    StartOfSystemHours,
+    StartOfMarketHours,
+    EndOfMarketHours,
+    EndOfSystemHours,
+    EndOfMessages,
+}
+
+impl TryFrom<u8> for SystemEvent {
+    type Error = ErrorCode;
+
+    fn try_from(code: u8) -> Result<Self, Self::Error> {
+        match code {
+            b'O' => Ok(SystemEvent::StartOfMessages),
+            b'S' => Ok(SystemEvent::StartOfSystemHours),
+            b'Q' => Ok(SystemEvent::StartOfMarketHours),
+            b'M' => Ok(SystemEvent::EndOfMarketHours),
+            b'E' => Ok(SystemEvent::EndOfSystemHours),
+            b'C' => Ok(SystemEvent::EndOfMessages),
+            _ => Err(ErrorCode::OtherError(format!("Unknown system event '{}'", code as char))),
+        }
+    }
+}
@@ -1,4 +1,4 @@
-
 pub mod order;
+pub mod order_pool;
+#[allow(clippy::module_inception)]
 pub mod orders;
-pub mod command;
\ No newline at end of file
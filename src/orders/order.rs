@@ -1,25 +1,18 @@
 use core::fmt;
-use std::sync::mpsc::Sender;
 
-use crate::{levels::{indexing::{LevelNode}, level::{Level, PopCurrent}}, order_book::order_book::OrderBook};
+use crate::{itch_handler::ITCHError, session::session_handler::SessionError, order_book::{auction_book::AuctionType, participant_depth::Mpid}};
 
-use super::command::Command;
-
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
 pub enum OrderSide {
+    #[default]
     Buy,
     Sell,
 }
 
-impl Default for OrderSide {
-    fn default() -> Self {
-        OrderSide::Buy // Assuming 'Buy' as a sensible default
-    }
-}
-
-#[derive(Clone, Debug, PartialEq, Copy)]
+#[derive(Clone, Debug, PartialEq, Copy, Default)]
 pub enum OrderType {
     Buy,
+    #[default]
     Market,
     Limit,
     Stop,
@@ -28,22 +21,49 @@ pub enum OrderType {
     TrailingStopLimit,
 }
 
-impl Default for OrderType {
-    fn default() -> Self {
-        OrderType::Market // Assuming 'Market' as a sensible default
-    }
-}
-
-#[derive(Clone, Debug, PartialEq, Copy)]
+#[derive(Clone, Debug, PartialEq, Copy, Default)]
 pub enum TimeInForce {
-    IOD,
+    // Good-Till-Cancelled
+    #[default]
+    GTC,
+    // Immediate-Or-Cancel
+    IOC,
+    // Fill-Or-Kill
     FOK,
-    // Other variants...
+    // All-Or-None
+    AON,
+    // Expires at the end of the trading day
+    DAY,
+    // Good-Till-Date, expires once the engine clock reaches the timestamp
+    GTD(u64),
+    // At-the-open, participates in the opening cross only
+    OPG,
+    // At-the-close, participates in the closing cross only
+    CLS,
 }
 
-impl Default for TimeInForce {
-    fn default() -> Self {
-     TimeInForce::IOD // Assuming 'IO' as a sensible default
+// Trailing distance or step of a trailing stop order, either in absolute price
+// ticks or in basis points of the market price
+#[derive(Clone, Debug, PartialEq, Copy, Default)]
+pub enum TrailingOffset {
+    // No offset, orders that do not trail keep their prices
+    #[default]
+    None,
+    Ticks(u64),
+    BasisPoints(u64),
+}
+
+impl TrailingOffset {
+    // Absolute price offset at the given market price
+    pub fn to_ticks(&self, market_price: u64) -> u64 {
+        match *self {
+            TrailingOffset::None => 0,
+            TrailingOffset::Ticks(ticks) => ticks,
+            TrailingOffset::BasisPoints(basis_points) => {
+                let ticks = market_price as u128 * basis_points as u128 / 10_000;
+                u64::try_from(ticks).unwrap_or(u64::MAX)
+            },
+        }
     }
 }
 
@@ -61,6 +81,14 @@ pub enum ErrorCode {
     OrderParameterInvalid,
     OrderQuantityInvalid,
     OrderCreationError,
+    // Market order found no opposite orders to match
+    NoLiquidity,
+    LevelDuplicate,
+    LevelNotFound,
+    // Price is not on the tick grid of a price ladder
+    LevelPriceInvalid,
+    MessageInvalid(ITCHError),
+    SessionInvalid(SessionError),
     DummyError,
     DefaultError, 
     OtherError(String),
@@ -94,14 +122,18 @@ pub struct Order {
     pub stop_price: u64,
     pub quantity: u64,
     pub executed_quantity: u64,
+    // Sum of price times quantity over all executions of the order
+    pub executed_value: u128,
     pub leaves_quantity: u64,
     pub hidden_quantity: u64,
     pub visible_quantity: u64,
     pub time_in_force: TimeInForce,
     pub max_visible_quantity: u64,
     pub slippage: u64,
-    pub trailing_distance: u64,
-    pub trailing_step: u64,
+    pub trailing_distance: TrailingOffset,
+    pub trailing_step: TrailingOffset,
+    // Market participant the order is attributed to, if disclosed
+    pub mpid: Option<Mpid>,
     //pub maybe_level: Level
 }
 
@@ -116,14 +148,16 @@ impl Default for Order {
             stop_price: 0,
             quantity: 0,
             executed_quantity: 0,
+            executed_value: 0,
             leaves_quantity: 0,
             time_in_force: Default::default(),
-            max_visible_quantity: 0,
-            slippage: 0,
-            trailing_distance: 0,
-            trailing_step: 0,
-            hidden_quantity: todo!(),
-            visible_quantity: todo!(),
+            max_visible_quantity: u64::MAX,
+            slippage: u64::MAX,
+            trailing_distance: Default::default(),
+            trailing_step: Default::default(),
+            hidden_quantity: 0,
+            visible_quantity: 0,
+            mpid: None,
         }
     }
 }
@@ -143,9 +177,14 @@ impl Order {
             return Err(ErrorCode::OrderQuantityInvalid);
         }
 
+        // Validate time in force
+        if self.time_in_force == TimeInForce::GTD(0) {
+            return Err(ErrorCode::OrderParameterInvalid);
+        }
+
         // Validate market order
         if self.is_market() {
-            if !self.is_ioc() && !self.is_fok() {
+            if !self.is_ioc() && !self.is_fok() && self.auction().is_none() {
                 return Err(ErrorCode::OrderParameterInvalid);
             }
             if self.is_iceberg() {
@@ -153,11 +192,39 @@ impl Order {
             }
         }
 
+        // Iceberg orders must display at least part of their quantity
+        if self.is_iceberg() && self.max_visible_quantity == 0 {
+            return Err(ErrorCode::OrderParameterInvalid);
+        }
+
         // Validate limit order
-        if self.is_limit() {
-            if self.is_slippage() {
-                return Err(ErrorCode::OrderParameterInvalid);
+        if self.is_limit() && self.is_slippage() {
+            return Err(ErrorCode::OrderParameterInvalid);
+        }
+
+        // Validate trailing stop order
+        if self.is_trailing_stop() || self.is_trailing_stop_limit() {
+            // Trailing distance is required and limited to 100% of the market price
+            match self.trailing_distance {
+                TrailingOffset::None | TrailingOffset::Ticks(0) | TrailingOffset::BasisPoints(0) => return Err(ErrorCode::OrderParameterInvalid),
+                TrailingOffset::BasisPoints(basis_points) if basis_points > 10_000 => return Err(ErrorCode::OrderParameterInvalid),
+                _ => {},
             }
+
+            // Trailing step must be shorter than the trailing distance
+            match (self.trailing_distance, self.trailing_step) {
+                (TrailingOffset::Ticks(distance), TrailingOffset::Ticks(step))
+                | (TrailingOffset::BasisPoints(distance), TrailingOffset::BasisPoints(step)) if step >= distance => {
+                    return Err(ErrorCode::OrderParameterInvalid);
+                },
+                (_, TrailingOffset::BasisPoints(step)) if step > 10_000 => return Err(ErrorCode::OrderParameterInvalid),
+                _ => {},
+            }
+        }
+
+        // Stop orders are never routed into auctions
+        if !self.is_market() && !self.is_limit() && self.auction().is_some() {
+            return Err(ErrorCode::OrderParameterInvalid);
         }
         Ok(())
     }
@@ -170,122 +237,114 @@ impl Order {
         self.order_type == OrderType::Market
     }
 
+    // Reserve quantity of an iceberg order that is not displayed yet
     pub fn hidden_quantity(&self) -> u64 {
-        if self.leaves_quantity > self.max_visible_quantity {
-            self.leaves_quantity - self.max_visible_quantity
-        } else {
-            0
-        }
+        self.leaves_quantity - self.visible_quantity()
     }
 
+    // Displayed slice of an iceberg order, or the whole leaves quantity otherwise
     pub fn visible_quantity(&self) -> u64 {
-        std::cmp::min(self.leaves_quantity, self.max_visible_quantity)
-    }
-
-    pub fn reduce_trailing_stop_order(&mut self, order_book: &mut OrderBook, quantity: u64, hidden: u64, visible: u64) -> Result<(), ErrorCode>
-    {
-        // Assuming we have a way to get a mutable reference to an order and its level.
-        // Update the price level volume
-        if let Some(level) = self.level {
-            // Directly manipulating the level here, assuming these methods modify the level and return a result for chaining.
-            self.subtract_volumes_from_level(level)
-                .and_then(|level| level.conditional_unlink_order(self))
-                .and_then(|level| level.process_level(order_book, self))
-                .map_err(|e| e) // Map the error if needed, or perform additional error handling
+        if self.is_iceberg() {
+            std::cmp::min(self.visible_quantity, self.leaves_quantity)
         } else {
-            // Handle the case where the order doesn't have an associated level
-            Err(ErrorCode::DefaultError)
+            self.leaves_quantity
         }
     }
 
-    pub fn subtract_volumes_from_level(&self, level: &mut Level) -> Result<&mut Level, ErrorCode> {
-        level.total_volume -= self.leaves_quantity;
-        level.hidden_volume -= self.hidden_quantity();
-        level.visible_volume -= self.visible_quantity;
-        Ok(level)
-    }
-
-    // Adds volumes to the Level based on the Order's quantities
-    pub fn add_volumes_to_level(&self, level: &mut Level) -> Result<&mut Level, ErrorCode> {
-        level.total_volume += self.leaves_quantity;
-        level.hidden_volume += self.hidden_quantity();
-        level.visible_volume += self.visible_quantity;
-        Ok(level)
+    // Refills the displayed slice from the reserve up to the maximal visible quantity
+    pub fn replenish(&mut self) {
+        self.visible_quantity = std::cmp::min(self.leaves_quantity, self.max_visible_quantity);
+        self.hidden_quantity = self.leaves_quantity - self.visible_quantity;
     }
 
-    // Unlinks the Order from the Level
-    pub fn unlink_order_from_level(&self, level: &mut Level) -> Result<&mut Level, ErrorCode> {
-        level.orders.pop_current(self);
-        Ok(level)
+    // Decreases the leaves quantity, consuming the displayed slice first
+    pub fn reduce_quantity(&mut self, quantity: u64) {
+        self.leaves_quantity -= quantity;
+        self.visible_quantity = self.visible_quantity.saturating_sub(quantity);
+        self.hidden_quantity = self.hidden_quantity();
     }
 
-    // Conditionally unlinks the Order from the Level
-    fn conditional_unlink_order_from_level(&self, level: &mut Level) -> Result<&mut Level, ErrorCode> {
-        if self.leaves_quantity == 0 {
-            self.unlink_order_from_level(level)
-        } else {
-            Ok(level)
-        }
+    // Checks if the displayed slice of an iceberg order is used up while its reserve is not
+    pub fn needs_replenish(&self) -> bool {
+        self.is_iceberg() && self.visible_quantity() == 0 && self.leaves_quantity > 0
     }
 }
 
 impl Order {
-    // Corresponds to the C++ constructor that accepts an Order
-    pub fn new(order: &Order) -> Self {
-        Self {
-            id: todo!(),
-            level_node: todo!(),
-            symbol_id: todo!(),
-            order_type: todo!(),
-            order_side: todo!(),
-            price: todo!(),
-            stop_price: todo!(),
-            quantity: todo!(),
-            executed_quantity: todo!(),
-            leaves_quantity: todo!(),
-            hidden_quantity: todo!(),
-            visible_quantity: todo!(),
-            time_in_force: todo!(),
-            max_visible_quantity: todo!(),
-            slippage: todo!(),
-            trailing_distance: todo!(),
-            trailing_step: todo!(),
-            level: todo!(),
+    // Creates a fully visible 'Good-Till-Cancel' limit order, as published by the ITCH feed
+    pub fn limit(id: u64, symbol_id: u64, order_side: OrderSide, price: u64, quantity: u64) -> Self {
+        Order {
+            id,
+            symbol_id,
+            order_type: OrderType::Limit,
+            order_side,
+            price,
+            quantity,
+            leaves_quantity: quantity,
+            visible_quantity: quantity,
+            max_visible_quantity: u64::MAX,
+            ..Default::default()
         }
     }
+
     pub fn is_limit(&self) -> bool {
-        // Add logic to determine if the order is a limit order
-        false
+        self.order_type == OrderType::Limit
     }
 
     pub fn is_buy(&self) -> bool {
-        // Add logic to determine if the order is a buy order
-        false
+        self.order_side == OrderSide::Buy
     }
 
     pub fn is_fok(&self) -> bool {
-        // Add logic to determine if the order is a Fill-or-Kill order
-        false
+        self.time_in_force == TimeInForce::FOK
     }
 
     pub fn is_iceberg(&self) -> bool {
-        // Add logic to determine if the order is an iceberg order
-        false
+        self.max_visible_quantity < u64::MAX
     }
 
+    // Market orders sweep the opposite side without a price bound unless slippage is set
     pub fn is_slippage(&self) -> bool {
-        // Add logic to determine if the order is subject to slippage
-        false
+        self.slippage < u64::MAX
+    }
+
+    // Increases the executed quantity of the order filled at the given price
+    pub fn execute(&mut self, price: u64, quantity: u64) {
+        self.executed_quantity += quantity;
+        self.executed_value += price as u128 * quantity as u128;
+    }
+
+    // Volume weighted average price of all executions of the order
+    pub fn average_fill_price(&self) -> Option<u64> {
+        if self.executed_quantity == 0 {
+            return None;
+        }
+        Some((self.executed_value / self.executed_quantity as u128) as u64)
     }
 
     pub fn is_aon(&self) -> bool {
-        // Add logic to determine if the order is an All-or-None order
-        false
+        self.time_in_force == TimeInForce::AON
     }
 
     pub fn is_ioc(&self) -> bool {
-        // Add logic to determine if the order is an Immediate-or-Cancel order
-        false
+        self.time_in_force == TimeInForce::IOC
+    }
+
+    pub fn is_day(&self) -> bool {
+        self.time_in_force == TimeInForce::DAY
+    }
+
+    pub fn is_gtd(&self) -> bool {
+        matches!(self.time_in_force, TimeInForce::GTD(_))
+    }
+
+    // Returns the auction the order is routed into, if any
+    pub fn auction(&self) -> Option<AuctionType> {
+        match self.time_in_force {
+            TimeInForce::OPG => Some(AuctionType::Opening),
+            TimeInForce::CLS => Some(AuctionType::Closing),
+            _ => None,
+        }
     }
 
     // Check if the order is a trailing stop
@@ -298,14 +357,4 @@ impl Order {
         matches!(self.order_type, OrderType::TrailingStopLimit)
     }
 
-    // Returns a mutable reference to the next Order
-    pub fn next(&self) -> Option<&Order> {
-        self.next()
-    }
-
-    // Returns a mutable reference to the next Order
-    pub fn next_mut(&mut self) -> Option<&mut Order> {
-        self.next_mut()
-    }
-
 }
@@ -0,0 +1,80 @@
+use generational_arena::{Arena, Index};
+
+use super::order::Order;
+
+// Typed handle of an order stored in an OrderPool. Handles of released orders are
+// never reused, so a stale handle simply finds no order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct OrderHandle(Index);
+
+// Slot of a pooled order. Resting orders are queued in their price level through the
+// prev/next links kept next to the order itself.
+#[derive(Debug)]
+struct PooledOrder {
+    order: Order,
+    prev: Option<OrderHandle>,
+    next: Option<OrderHandle>,
+    // Set while the order is queued in a level, even as its only order without links
+    queued: bool,
+}
+
+// Slab of all orders resting in the market. Slots of released orders are recycled,
+// so orders are stored without an allocation of their own.
+#[derive(Debug, Default)]
+pub struct OrderPool {
+    orders: Arena<PooledOrder>,
+}
+
+impl OrderPool {
+    pub fn len(&self) -> usize {
+        self.orders.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.orders.is_empty()
+    }
+
+    pub fn allocate(&mut self, order: Order) -> OrderHandle {
+        OrderHandle(self.orders.insert(PooledOrder { order, prev: None, next: None, queued: false }))
+    }
+
+    // Queued orders must be unlinked from their level before they are released
+    pub fn release(&mut self, handle: OrderHandle) -> Option<Order> {
+        self.orders.remove(handle.0).map(|slot| slot.order)
+    }
+
+    pub fn get(&self, handle: OrderHandle) -> Option<&Order> {
+        self.orders.get(handle.0).map(|slot| &slot.order)
+    }
+
+    pub fn get_mut(&mut self, handle: OrderHandle) -> Option<&mut Order> {
+        self.orders.get_mut(handle.0).map(|slot| &mut slot.order)
+    }
+
+    // Previous and next orders queued in the same level
+    pub(crate) fn links(&self, handle: OrderHandle) -> Option<(Option<OrderHandle>, Option<OrderHandle>)> {
+        self.orders.get(handle.0).map(|slot| (slot.prev, slot.next))
+    }
+
+    pub(crate) fn is_queued(&self, handle: OrderHandle) -> Option<bool> {
+        self.orders.get(handle.0).map(|slot| slot.queued)
+    }
+
+    pub(crate) fn set_queued(&mut self, handle: OrderHandle, queued: bool) {
+        if let Some(slot) = self.orders.get_mut(handle.0) {
+            slot.queued = queued;
+        }
+    }
+
+    pub(crate) fn set_prev(&mut self, handle: OrderHandle, prev: Option<OrderHandle>) {
+        if let Some(slot) = self.orders.get_mut(handle.0) {
+            slot.prev = prev;
+        }
+    }
+
+    pub(crate) fn set_next(&mut self, handle: OrderHandle, next: Option<OrderHandle>) {
+        if let Some(slot) = self.orders.get_mut(handle.0) {
+            slot.next = next;
+        }
+    }
+}
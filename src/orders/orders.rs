@@ -1,54 +1,80 @@
-use std::{collections::HashMap, ops::{Deref, DerefMut}};
+use std::{collections::HashMap, ops::Deref};
 
-use super::order::{ErrorCode, Order};
+use super::{order::{ErrorCode, Order}, order_pool::{OrderHandle, OrderPool}};
 
 pub trait OrderOps
 {
-    fn insert_order(&mut self, id: &u64, order: &Order) -> Option<Order>;
+    fn insert_order(&mut self, order: &Order) -> Result<OrderHandle, ErrorCode>;
     fn remove_order(&mut self, id: &u64) -> Option<Order>;
     fn get_order(&self, id: u64) -> Result<&Order, ErrorCode>;
     fn get_mut_order(&mut self, id: u64) -> Result<&mut Order, ErrorCode>;
+    fn get_handle(&self, id: u64) -> Result<OrderHandle, ErrorCode>;
+    fn get_order_at(&self, handle: OrderHandle) -> Result<&Order, ErrorCode>;
+    fn get_mut_order_at(&mut self, handle: OrderHandle) -> Result<&mut Order, ErrorCode>;
 }
 
-impl OrderOps for Orders 
+impl OrderOps for Orders
 {
-    fn insert_order(&mut self, id: &u64, order: &Order) -> Option<Order> {
-        self.insert(*id, *order)
+    fn insert_order(&mut self, order: &Order) -> Result<OrderHandle, ErrorCode> {
+        if self.handles.contains_key(&order.id) {
+            return Err(ErrorCode::OrderDuplicate);
+        }
+        let handle = self.pool.allocate(order.clone());
+        self.handles.insert(order.id, handle);
+        Ok(handle)
     }
 
     fn remove_order(&mut self, id: &u64) -> Option<Order> {
-        self.remove(&id)
+        let handle = self.handles.remove(id)?;
+        self.pool.release(handle)
     }
 
     fn get_order(&self, id: u64) -> Result<&Order, ErrorCode> {
-        self.orders.get(&id).ok_or(ErrorCode::OrderNotFound)
+        self.get_order_at(self.get_handle(id)?)
     }
 
     fn get_mut_order(&mut self, id: u64) -> Result<&mut Order, ErrorCode> {
-        self.orders.get_mut(&id).ok_or(ErrorCode::OrderNotFound) // New method implementation
+        self.get_mut_order_at(self.get_handle(id)?)
+    }
+
+    fn get_handle(&self, id: u64) -> Result<OrderHandle, ErrorCode> {
+        self.handles.get(&id).copied().ok_or(ErrorCode::OrderNotFound)
+    }
+
+    fn get_order_at(&self, handle: OrderHandle) -> Result<&Order, ErrorCode> {
+        self.pool.get(handle).ok_or(ErrorCode::OrderNotFound)
+    }
+
+    fn get_mut_order_at(&mut self, handle: OrderHandle) -> Result<&mut Order, ErrorCode> {
+        self.pool.get_mut(handle).ok_or(ErrorCode::OrderNotFound)
     }
 }
 
+// Orders are stored once in the pool and looked up by their external Id
 #[derive(Default)]
 pub struct Orders
 {
-    orders: HashMap<u64, Order>
+    pool: OrderPool,
+    handles: HashMap<u64, OrderHandle>,
 }
 
-
-impl Deref for Orders 
+impl Orders
 {
-    type Target = HashMap<u64, Order>;
+    // Pool the orders are stored in, resting orders are queued through its slots
+    pub fn pool(&self) -> &OrderPool {
+        &self.pool
+    }
 
-    fn deref(&self) -> &Self::Target {
-        &self.orders
+    pub(crate) fn pool_mut(&mut self) -> &mut OrderPool {
+        &mut self.pool
     }
 }
 
-impl DerefMut for Orders 
+impl Deref for Orders
 {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.orders
+    type Target = HashMap<u64, OrderHandle>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.handles
     }
 }
-
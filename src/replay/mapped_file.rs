@@ -0,0 +1,71 @@
+use std::fs::File;
+use std::io;
+use std::path::Path;
+
+use memmap2::Mmap;
+
+use crate::itch_handler::ITCHError;
+
+// Read-only memory map of a length-prefixed ITCH 5.0 file
+pub struct MappedITCHFile {
+    mmap: Mmap,
+}
+
+impl MappedITCHFile {
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let file = File::open(path)?;
+        // SAFETY: the mapping is read-only and replay files are not modified while being replayed
+        let mmap = unsafe { Mmap::map(&file)? };
+        Ok(MappedITCHFile { mmap })
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.mmap
+    }
+
+    pub fn frames(&self) -> Frames<'_> {
+        Frames::new(&self.mmap)
+    }
+}
+
+// Framing pass over a length-prefixed buffer yielding each message without copying
+pub struct Frames<'a> {
+    data: &'a [u8],
+    index: usize,
+}
+
+impl<'a> Frames<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Frames { data, index: 0 }
+    }
+}
+
+impl<'a> Iterator for Frames<'a> {
+    type Item = Result<&'a [u8], ITCHError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let remaining = self.data.len() - self.index;
+            if remaining == 0 {
+                return None;
+            }
+            if remaining < 2 {
+                self.index = self.data.len();
+                return Some(Err(ITCHError::TruncatedMessage { expected: 2, actual: remaining }));
+            }
+
+            let size = u16::from_be_bytes([self.data[self.index], self.data[self.index + 1]]) as usize;
+            let start = self.index + 2;
+            if start + size > self.data.len() {
+                self.index = self.data.len();
+                return Some(Err(ITCHError::TruncatedMessage { expected: size, actual: self.data.len() - start }));
+            }
+            self.index = start + size;
+
+            // Zero sized messages carry nothing to process
+            if size > 0 {
+                return Some(Ok(&self.data[start..start + size]));
+            }
+        }
+    }
+}
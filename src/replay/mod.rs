@@ -0,0 +1,2 @@
+pub mod mapped_file;
+pub mod sharded_replay;
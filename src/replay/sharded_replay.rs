@@ -0,0 +1,155 @@
+use std::io;
+use std::sync::mpsc::{sync_channel, Receiver, SyncSender};
+use std::thread;
+
+use crate::{itch_handler::ITCHHandler, market_executors::market_manager::MarketManager, market_handler::Handler, orders::order::ErrorCode};
+
+use super::mapped_file::Frames;
+
+// Replays a full ITCH buffer across worker threads. The calling thread runs the
+// framing pass and routes every message by stock locate, so each worker owns a
+// disjoint subset of order books and sees its symbols in feed order. Messages
+// without a stock locate (system events, MWCB) are broadcast to every worker.
+pub struct ShardedReplay {
+    workers: usize,
+    batch_size: usize,
+    queue_depth: usize,
+}
+
+impl Default for ShardedReplay {
+    fn default() -> Self {
+        ShardedReplay {
+            workers: thread::available_parallelism().map_or(1, |workers| workers.get()),
+            batch_size: 4096,
+            queue_depth: 64,
+        }
+    }
+}
+
+impl ShardedReplay {
+    pub fn new(workers: usize) -> Self {
+        ShardedReplay {
+            workers: std::cmp::max(workers, 1),
+            ..Default::default()
+        }
+    }
+
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = std::cmp::max(batch_size, 1);
+        self
+    }
+
+    pub fn with_queue_depth(mut self, queue_depth: usize) -> Self {
+        self.queue_depth = std::cmp::max(queue_depth, 1);
+        self
+    }
+
+    pub fn workers(&self) -> usize {
+        self.workers
+    }
+
+    pub fn shard_of(&self, stock_locate: u16) -> usize {
+        stock_locate as usize % self.workers
+    }
+
+    // Each worker builds its own market manager and hands its ITCH handler to
+    // `finish` once the buffer is exhausted; the results are returned in shard order.
+    pub fn run<H, F, R>(&self, data: &[u8], finish: F) -> io::Result<Vec<R>>
+    where
+        H: Handler + Default,
+        F: Fn(usize, ITCHHandler<H>) -> R + Sync,
+        R: Send,
+    {
+        thread::scope(|scope| {
+            let mut senders: Vec<SyncSender<Vec<&[u8]>>> = Vec::with_capacity(self.workers);
+            let mut workers = Vec::with_capacity(self.workers);
+
+            for shard in 0..self.workers {
+                let (sender, receiver) = sync_channel(self.queue_depth);
+                senders.push(sender);
+                let finish = &finish;
+                workers.push(scope.spawn(move || Self::run_worker::<H, F, R>(shard, receiver, finish)));
+            }
+
+            let framing = self.route(data, &senders);
+
+            // Closing the queues lets the workers drain and finish
+            drop(senders);
+
+            let mut results = Vec::with_capacity(self.workers);
+            for worker in workers {
+                let result = worker
+                    .join()
+                    .map_err(|_| io::Error::other("ITCH replay worker panicked"))?;
+                results.push(result.map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?);
+            }
+
+            // Worker errors take precedence since they stop the framing pass early
+            framing?;
+            Ok(results)
+        })
+    }
+
+    fn route<'a>(&self, data: &'a [u8], senders: &[SyncSender<Vec<&'a [u8]>>]) -> io::Result<()> {
+        let mut batches: Vec<Vec<&'a [u8]>> = (0..self.workers)
+            .map(|_| Vec::with_capacity(self.batch_size))
+            .collect();
+
+        for frame in Frames::new(data) {
+            let message = frame.map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+
+            // Every message starts with the type followed by the stock locate
+            let stock_locate = if message.len() >= 3 {
+                u16::from_be_bytes([message[1], message[2]])
+            } else {
+                0
+            };
+
+            if stock_locate == 0 {
+                for shard in 0..self.workers {
+                    Self::push(&mut batches[shard], message, &senders[shard], self.batch_size)?;
+                }
+            } else {
+                let shard = self.shard_of(stock_locate);
+                Self::push(&mut batches[shard], message, &senders[shard], self.batch_size)?;
+            }
+        }
+
+        for (batch, sender) in batches.into_iter().zip(senders) {
+            if !batch.is_empty() {
+                Self::send(sender, batch)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn push<'a>(batch: &mut Vec<&'a [u8]>, message: &'a [u8], sender: &SyncSender<Vec<&'a [u8]>>, batch_size: usize) -> io::Result<()> {
+        batch.push(message);
+        if batch.len() >= batch_size {
+            let full = std::mem::replace(batch, Vec::with_capacity(batch_size));
+            Self::send(sender, full)?;
+        }
+        Ok(())
+    }
+
+    fn send<'a>(sender: &SyncSender<Vec<&'a [u8]>>, batch: Vec<&'a [u8]>) -> io::Result<()> {
+        // A closed queue means the worker already stopped on an error it will report
+        sender
+            .send(batch)
+            .map_err(|_| io::Error::other("ITCH replay worker stopped"))
+    }
+
+    fn run_worker<H, F, R>(shard: usize, receiver: Receiver<Vec<&[u8]>>, finish: &F) -> Result<R, ErrorCode>
+    where
+        H: Handler + Default,
+        F: Fn(usize, ITCHHandler<H>) -> R,
+    {
+        let mut handler = ITCHHandler::new(MarketManager::<H>::default());
+        for batch in receiver {
+            for message in batch {
+                handler.replay_message(message)?;
+            }
+        }
+        Ok(finish(shard, handler))
+    }
+}
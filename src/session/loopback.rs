@@ -0,0 +1,40 @@
+use std::io::{self, Write};
+use std::net::{TcpListener, TcpStream, UdpSocket};
+
+// Local stand-ins for the multicast and TCP feeds, bound to ephemeral ports on 127.0.0.1
+
+pub struct UdpLoopback {
+    pub sender: UdpSocket,
+    pub receiver: UdpSocket,
+}
+
+impl UdpLoopback {
+    pub fn new() -> io::Result<Self> {
+        let receiver = UdpSocket::bind("127.0.0.1:0")?;
+        let sender = UdpSocket::bind("127.0.0.1:0")?;
+        sender.connect(receiver.local_addr()?)?;
+        Ok(UdpLoopback { sender, receiver })
+    }
+
+    pub fn send(&self, packet: &[u8]) -> io::Result<usize> {
+        self.sender.send(packet)
+    }
+}
+
+pub struct TcpLoopback {
+    pub server: TcpStream,
+    pub client: TcpStream,
+}
+
+impl TcpLoopback {
+    pub fn new() -> io::Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let client = TcpStream::connect(listener.local_addr()?)?;
+        let (server, _) = listener.accept()?;
+        Ok(TcpLoopback { server, client })
+    }
+
+    pub fn send(&mut self, packet: &[u8]) -> io::Result<()> {
+        self.server.write_all(packet)
+    }
+}
@@ -0,0 +1,7 @@
+pub mod session_handler;
+pub mod sequence_tracker;
+pub mod mold_udp64;
+pub mod soup_bin_tcp;
+pub mod loopback;
+pub mod recovery;
+pub mod sequencer;
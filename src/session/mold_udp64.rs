@@ -0,0 +1,213 @@
+use std::io;
+use std::net::UdpSocket;
+
+use crate::orders::order::ErrorCode;
+
+use super::{sequence_tracker::SequenceTracker, session_handler::{SessionError, SessionHandler}};
+
+pub const HEADER_SIZE: usize = 20;
+pub const END_OF_SESSION: u16 = 0xFFFF;
+pub const MAX_PACKET_SIZE: usize = 65535;
+
+// Zero-copy view over a downstream MoldUDP64 packet
+#[derive(Debug, Clone, Copy)]
+pub struct MoldUDP64Packet<'a> {
+    buffer: &'a [u8],
+}
+
+impl<'a> MoldUDP64Packet<'a> {
+    pub fn new(buffer: &'a [u8]) -> Result<Self, SessionError> {
+        if buffer.len() < HEADER_SIZE {
+            return Err(SessionError::TruncatedPacket { expected: HEADER_SIZE, actual: buffer.len() });
+        }
+        let packet = MoldUDP64Packet { buffer };
+
+        // Validate the message blocks up front so iteration cannot fail
+        let expected = packet.message_count();
+        if expected != END_OF_SESSION {
+            let mut actual = 0;
+            let mut index = HEADER_SIZE;
+            while index < buffer.len() {
+                if index + 2 > buffer.len() {
+                    return Err(SessionError::TruncatedPacket { expected: index + 2, actual: buffer.len() });
+                }
+                let size = u16::from_be_bytes([buffer[index], buffer[index + 1]]) as usize;
+                index += 2 + size;
+                if index > buffer.len() {
+                    return Err(SessionError::TruncatedPacket { expected: index, actual: buffer.len() });
+                }
+                actual += 1;
+            }
+            if actual != expected {
+                return Err(SessionError::MessageCountMismatch { expected, actual });
+            }
+        }
+        Ok(packet)
+    }
+
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.buffer
+    }
+
+    pub fn session(&self) -> [u8; 10] {
+        let mut session = [0; 10];
+        session.copy_from_slice(&self.buffer[0..10]);
+        session
+    }
+
+    // Sequence number of the first message in the packet
+    pub fn sequence_number(&self) -> u64 {
+        let mut bytes = [0; 8];
+        bytes.copy_from_slice(&self.buffer[10..18]);
+        u64::from_be_bytes(bytes)
+    }
+
+    pub fn message_count(&self) -> u16 {
+        u16::from_be_bytes([self.buffer[18], self.buffer[19]])
+    }
+
+    pub fn is_heartbeat(&self) -> bool {
+        self.message_count() == 0
+    }
+
+    pub fn is_end_of_session(&self) -> bool {
+        self.message_count() == END_OF_SESSION
+    }
+
+    pub fn messages(&self) -> MoldUDP64Messages<'a> {
+        let index = if self.is_end_of_session() { self.buffer.len() } else { HEADER_SIZE };
+        MoldUDP64Messages { buffer: self.buffer, index, sequence: self.sequence_number() }
+    }
+
+    pub fn encode(session: &[u8; 10], sequence_number: u64, messages: &[&[u8]]) -> Vec<u8> {
+        let size = HEADER_SIZE + messages.iter().map(|message| message.len() + 2).sum::<usize>();
+        let mut packet = Vec::with_capacity(size);
+        packet.extend_from_slice(session);
+        packet.extend_from_slice(&sequence_number.to_be_bytes());
+        packet.extend_from_slice(&(messages.len() as u16).to_be_bytes());
+        for message in messages {
+            packet.extend_from_slice(&(message.len() as u16).to_be_bytes());
+            packet.extend_from_slice(message);
+        }
+        packet
+    }
+
+    pub fn encode_end_of_session(session: &[u8; 10], sequence_number: u64) -> Vec<u8> {
+        let mut packet = Self::encode(session, sequence_number, &[]);
+        packet[18..20].copy_from_slice(&END_OF_SESSION.to_be_bytes());
+        packet
+    }
+}
+
+// Yields (sequence number, message) pairs of a validated packet
+pub struct MoldUDP64Messages<'a> {
+    buffer: &'a [u8],
+    index: usize,
+    sequence: u64,
+}
+
+impl<'a> Iterator for MoldUDP64Messages<'a> {
+    type Item = (u64, &'a [u8]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index >= self.buffer.len() {
+            return None;
+        }
+        let size = u16::from_be_bytes([self.buffer[self.index], self.buffer[self.index + 1]]) as usize;
+        let start = self.index + 2;
+        self.index = start + size;
+
+        let sequence = self.sequence;
+        self.sequence += 1;
+        Some((sequence, &self.buffer[start..start + size]))
+    }
+}
+
+// Unwraps MoldUDP64 packets of a single session into the ITCH message stream
+pub struct MoldUDP64Session {
+    session: Option<[u8; 10]>,
+    tracker: SequenceTracker,
+    ended: bool,
+    buffer: Vec<u8>,
+}
+
+impl Default for MoldUDP64Session {
+    fn default() -> Self {
+        MoldUDP64Session {
+            session: None,
+            tracker: SequenceTracker::default(),
+            ended: false,
+            buffer: vec![0; MAX_PACKET_SIZE],
+        }
+    }
+}
+
+impl MoldUDP64Session {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Only accept packets of the given session, starting from the given sequence number
+    pub fn with_session(session: [u8; 10], expected: u64) -> Self {
+        MoldUDP64Session {
+            session: Some(session),
+            tracker: SequenceTracker::new(expected),
+            ..Default::default()
+        }
+    }
+
+    pub fn session(&self) -> Option<[u8; 10]> {
+        self.session
+    }
+
+    pub fn expected_sequence_number(&self) -> Option<u64> {
+        self.tracker.expected()
+    }
+
+    pub fn is_ended(&self) -> bool {
+        self.ended
+    }
+
+    pub fn process_packet<H: SessionHandler>(&mut self, buffer: &[u8], handler: &mut H) -> Result<(), ErrorCode> {
+        let packet = MoldUDP64Packet::new(buffer)?;
+
+        let session = packet.session();
+        match self.session {
+            Some(expected) if expected != session => {
+                return Err(SessionError::SessionMismatch { expected, actual: session }.into());
+            }
+            Some(_) => {}
+            None => self.session = Some(session),
+        }
+
+        if packet.is_end_of_session() {
+            self.tracker.advance_to(packet.sequence_number(), handler)?;
+            self.ended = true;
+            handler.on_end_of_session();
+            return Ok(());
+        }
+
+        if packet.is_heartbeat() {
+            self.tracker.advance_to(packet.sequence_number(), handler)?;
+            return Ok(());
+        }
+
+        for (sequence, message) in packet.messages() {
+            if self.tracker.accept(sequence, handler)? {
+                handler.on_message(sequence, message)?;
+            }
+        }
+        Ok(())
+    }
+
+    // Receives and processes one datagram. Returns false once the session has ended.
+    pub fn receive<H: SessionHandler>(&mut self, socket: &UdpSocket, handler: &mut H) -> io::Result<bool> {
+        let mut buffer = std::mem::take(&mut self.buffer);
+        let result = socket.recv(&mut buffer).and_then(|size| {
+            self.process_packet(&buffer[..size], handler)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))
+        });
+        self.buffer = buffer;
+        result.map(|_| !self.ended)
+    }
+}
@@ -0,0 +1,98 @@
+use std::io;
+use std::net::{ToSocketAddrs, UdpSocket};
+
+use crate::{orders::order::ErrorCode, replay::mapped_file::MappedITCHFile};
+
+// Source of retransmissions for the messages lost in a feed gap
+pub trait RecoverySource {
+    // Requests the messages in [from, to). Sources that can answer immediately push
+    // them to `recovered`; the others deliver them later through Sequencer::on_recovered.
+    fn request(&mut self, from: u64, to: u64, recovered: &mut Vec<(u64, Vec<u8>)>) -> Result<(), ErrorCode>;
+}
+
+// Leaves gaps open, e.g. when the feed is replayed without a retransmission service
+#[derive(Debug, Default, Clone, Copy)]
+pub struct NoRecovery;
+
+impl RecoverySource for NoRecovery {
+    fn request(&mut self, _from: u64, _to: u64, _recovered: &mut Vec<(u64, Vec<u8>)>) -> Result<(), ErrorCode> {
+        Ok(())
+    }
+}
+
+// Rewinds a length-prefixed ITCH file in which the n-th message carries sequence number n
+pub struct FileRewind {
+    file: MappedITCHFile,
+    // Sequence number and offset of the next message, so forward requests do not rescan the file
+    position: (u64, usize),
+}
+
+impl FileRewind {
+    pub fn new(file: MappedITCHFile) -> Self {
+        FileRewind { file, position: (1, 0) }
+    }
+}
+
+impl RecoverySource for FileRewind {
+    fn request(&mut self, from: u64, to: u64, recovered: &mut Vec<(u64, Vec<u8>)>) -> Result<(), ErrorCode> {
+        if from < self.position.0 {
+            self.position = (1, 0);
+        }
+
+        let (mut sequence, mut offset) = self.position;
+        let data = self.file.as_bytes();
+        while sequence < to {
+            if offset + 2 > data.len() {
+                break;
+            }
+            let size = u16::from_be_bytes([data[offset], data[offset + 1]]) as usize;
+            let start = offset + 2;
+            if start + size > data.len() {
+                break;
+            }
+            if sequence >= from {
+                recovered.push((sequence, data[start..start + size].to_vec()));
+            }
+            offset = start + size;
+            sequence += 1;
+        }
+        self.position = (sequence, offset);
+        Ok(())
+    }
+}
+
+// Sends MoldUDP64 re-request packets; the responses arrive as regular MoldUDP64
+// packets on the same socket and are fed to the Sequencer through a MoldUDP64Session
+// or Sequencer::on_recovered_packet.
+pub struct MoldUDP64RequestServer {
+    socket: UdpSocket,
+    session: [u8; 10],
+}
+
+impl MoldUDP64RequestServer {
+    pub fn new<A: ToSocketAddrs>(socket: UdpSocket, server: A, session: [u8; 10]) -> io::Result<Self> {
+        socket.connect(server)?;
+        Ok(MoldUDP64RequestServer { socket, session })
+    }
+
+    pub fn socket(&self) -> &UdpSocket {
+        &self.socket
+    }
+}
+
+impl RecoverySource for MoldUDP64RequestServer {
+    fn request(&mut self, from: u64, to: u64, _recovered: &mut Vec<(u64, Vec<u8>)>) -> Result<(), ErrorCode> {
+        let mut sequence = from;
+        while sequence < to {
+            // The message count of a request excludes the end of session marker
+            let count = std::cmp::min(to - sequence, (u16::MAX - 1) as u64);
+            let mut packet = Vec::with_capacity(20);
+            packet.extend_from_slice(&self.session);
+            packet.extend_from_slice(&sequence.to_be_bytes());
+            packet.extend_from_slice(&(count as u16).to_be_bytes());
+            self.socket.send(&packet).map_err(|e| ErrorCode::OtherError(e.to_string()))?;
+            sequence += count;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,73 @@
+use crate::orders::order::ErrorCode;
+
+use super::session_handler::SessionHandler;
+
+// Tracks the next expected sequence number of a session. Until the first message
+// is seen, any sequence number is accepted as the starting point.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct SequenceTracker {
+    expected: Option<u64>,
+}
+
+impl SequenceTracker {
+    pub fn new(expected: u64) -> Self {
+        SequenceTracker { expected: Some(expected) }
+    }
+
+    pub fn expected(&self) -> Option<u64> {
+        self.expected
+    }
+
+    pub fn reset(&mut self, expected: u64) {
+        self.expected = Some(expected);
+    }
+
+    // Returns true if the message with the given sequence number should be delivered.
+    // Gaps and duplicates are reported to the handler.
+    pub fn accept<H: SessionHandler>(&mut self, sequence: u64, handler: &mut H) -> Result<bool, ErrorCode> {
+        // Sequencing handlers take every message, retransmissions arrive behind the live ones
+        if handler.is_sequencing() {
+            self.expected = Some(std::cmp::max(self.expected.unwrap_or(0), sequence + 1));
+            return Ok(true);
+        }
+
+        match self.expected {
+            Some(expected) if sequence < expected => {
+                handler.on_duplicate(sequence);
+                Ok(false)
+            }
+            Some(expected) => {
+                // The gap stays reported even if the handler fails to handle it
+                self.expected = Some(sequence + 1);
+                if sequence > expected {
+                    handler.on_gap(expected, sequence)?;
+                }
+                Ok(true)
+            }
+            None => {
+                self.expected = Some(sequence + 1);
+                Ok(true)
+            }
+        }
+    }
+
+    // Heartbeats and logins carry the next sequence number the server will send
+    pub fn advance_to<H: SessionHandler>(&mut self, next: u64, handler: &mut H) -> Result<(), ErrorCode> {
+        if handler.is_sequencing() {
+            self.expected = Some(std::cmp::max(self.expected.unwrap_or(0), next));
+            return handler.on_next_sequence(next);
+        }
+
+        match self.expected {
+            Some(expected) if next > expected => {
+                self.expected = Some(next);
+                handler.on_gap(expected, next)
+            }
+            Some(_) => Ok(()),
+            None => {
+                self.expected = Some(next);
+                Ok(())
+            }
+        }
+    }
+}
@@ -0,0 +1,222 @@
+use std::collections::BTreeMap;
+
+use crate::{itch_handler::ITCHHandler, market_handler::Handler, orders::order::ErrorCode};
+
+use super::{mold_udp64::MoldUDP64Packet, recovery::RecoverySource, session_handler::SessionHandler};
+
+// Range of sequence numbers [from, to) that was missing when the gap was detected
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Gap {
+    pub from: u64,
+    pub to: u64,
+}
+
+// Default number of messages buffered behind a gap before the gap is abandoned
+pub const DEFAULT_MAX_PENDING: usize = 1 << 16;
+
+// Applies sequenced messages to the ITCH handler strictly in order. Messages that
+// arrive past a gap are buffered while the missing range is requested from the
+// recovery source, and every order book is marked stale until the gap is filled.
+// A gap that is abandoned, explicitly or once the buffer is full, leaves the books
+// stale until they are resynchronized and `mark_stale(false)` is called.
+pub struct Sequencer<H, R> {
+    pub handler: ITCHHandler<H>,
+    pub recovery: R,
+    expected: Option<u64>,
+    // One past the highest sequence number received or requested so far
+    received: u64,
+    pending: BTreeMap<u64, Vec<u8>>,
+    max_pending: usize,
+    gap: Option<Gap>,
+    // Books already stale when the gap opened stay stale once it is recovered
+    stale_before_gap: bool,
+}
+
+impl<H, R> Sequencer<H, R>
+where
+    H: Handler,
+    R: RecoverySource,
+{
+    pub fn new(handler: ITCHHandler<H>, recovery: R) -> Self {
+        Sequencer {
+            handler,
+            recovery,
+            expected: None,
+            received: 0,
+            pending: BTreeMap::new(),
+            max_pending: DEFAULT_MAX_PENDING,
+            gap: None,
+            stale_before_gap: false,
+        }
+    }
+
+    // Start from a known sequence number instead of the first one received
+    pub fn with_expected(mut self, expected: u64) -> Self {
+        self.expected = Some(expected);
+        self.received = expected;
+        self
+    }
+
+    // Limits the number of messages buffered behind a gap
+    pub fn with_max_pending(mut self, max_pending: usize) -> Self {
+        self.max_pending = max_pending;
+        self
+    }
+
+    pub fn expected_sequence_number(&self) -> Option<u64> {
+        self.expected
+    }
+
+    pub fn gap(&self) -> Option<Gap> {
+        self.gap
+    }
+
+    pub fn is_recovering(&self) -> bool {
+        self.gap.is_some()
+    }
+
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn on_sequenced(&mut self, sequence: u64, message: &[u8]) -> Result<(), ErrorCode> {
+        let expected = match self.expected {
+            Some(expected) => expected,
+            None => {
+                self.expected = Some(sequence);
+                self.received = sequence;
+                sequence
+            }
+        };
+
+        // Duplicates of applied or buffered messages are dropped
+        if sequence < expected || self.pending.contains_key(&sequence) {
+            return Ok(());
+        }
+
+        if sequence > expected {
+            // A full buffer gives up on the gap and handles the message past it
+            if !self.pending.is_empty() && self.pending.len() >= self.max_pending {
+                self.abandon_gap()?;
+                return self.on_sequenced(sequence, message);
+            }
+
+            self.pending.insert(sequence, message.to_vec());
+            let from = self.received;
+            self.received = std::cmp::max(self.received, sequence + 1);
+            if from < sequence {
+                self.request(from, sequence)?;
+            }
+            return Ok(());
+        }
+
+        self.handler.replay_message(message)?;
+        self.expected = Some(sequence + 1);
+        self.received = std::cmp::max(self.received, sequence + 1);
+        self.drain()
+    }
+
+    // Retransmitted messages go through the same ordering as live ones
+    pub fn on_recovered(&mut self, sequence: u64, message: &[u8]) -> Result<(), ErrorCode> {
+        self.on_sequenced(sequence, message)
+    }
+
+    pub fn on_recovered_packet(&mut self, packet: &[u8]) -> Result<(), ErrorCode> {
+        let packet = MoldUDP64Packet::new(packet)?;
+        for (sequence, message) in packet.messages() {
+            self.on_recovered(sequence, message)?;
+        }
+        Ok(())
+    }
+
+    // Reports that [from, to) is missing, typically detected by a heartbeat
+    pub fn on_missing(&mut self, from: u64, to: u64) -> Result<(), ErrorCode> {
+        let from = std::cmp::max(from, self.received);
+        if self.expected.is_none() || from >= to {
+            return Ok(());
+        }
+        self.received = to;
+        self.request(from, to)
+    }
+
+    // Gives up on recovering the open gap. Buffered messages are applied in order,
+    // skipping the sequence numbers never received, and the books stay stale.
+    pub fn abandon_gap(&mut self) -> Result<(), ErrorCode> {
+        let Some(gap) = self.gap.take() else {
+            return Ok(());
+        };
+
+        while let Some((sequence, message)) = self.pending.pop_first() {
+            self.expected = Some(sequence + 1);
+            self.handler.replay_message(&message)?;
+        }
+        // Messages of the abandoned range arriving later are dropped as duplicates
+        self.expected = Some(self.received);
+
+        // Call the corresponding MarketHandler
+        self.handler.manager.handler.on_gap_close(gap.from, gap.to, false);
+        Ok(())
+    }
+
+    fn request(&mut self, from: u64, to: u64) -> Result<(), ErrorCode> {
+        match self.gap.as_mut() {
+            Some(gap) => gap.to = std::cmp::max(gap.to, to),
+            None => {
+                self.gap = Some(Gap { from, to });
+                self.stale_before_gap = self.handler.is_stale();
+                self.handler.mark_stale(true);
+                // Call the corresponding MarketHandler
+                self.handler.manager.handler.on_gap_open(from, to);
+            }
+        }
+
+        let mut recovered = Vec::new();
+        self.recovery.request(from, to, &mut recovered)?;
+        for (sequence, message) in recovered {
+            self.on_recovered(sequence, &message)?;
+        }
+        Ok(())
+    }
+
+    fn drain(&mut self) -> Result<(), ErrorCode> {
+        while let Some(mut expected) = self.expected {
+            let Some(message) = self.pending.remove(&expected) else {
+                break;
+            };
+            self.handler.replay_message(&message)?;
+            expected += 1;
+            self.expected = Some(expected);
+        }
+
+        if let (Some(gap), Some(expected)) = (self.gap, self.expected) {
+            if expected >= self.received {
+                self.gap = None;
+                self.handler.mark_stale(self.stale_before_gap);
+                // Call the corresponding MarketHandler
+                self.handler.manager.handler.on_gap_close(gap.from, gap.to, true);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<H, R> SessionHandler for Sequencer<H, R>
+where
+    H: Handler,
+    R: RecoverySource,
+{
+    fn on_message(&mut self, sequence: u64, message: &[u8]) -> Result<(), ErrorCode> {
+        self.on_sequenced(sequence, message)
+    }
+
+    // The sequencer is the only place tracking the sequence, so retransmissions received
+    // through the session are not dropped as duplicates
+    fn is_sequencing(&self) -> bool {
+        true
+    }
+
+    // Messages announced by heartbeats but never received are missing
+    fn on_next_sequence(&mut self, next: u64) -> Result<(), ErrorCode> {
+        self.on_missing(self.received, next)
+    }
+}
@@ -0,0 +1,75 @@
+use std::fmt;
+
+use crate::{itch_handler::ITCHHandler, market_handler::Handler, orders::order::ErrorCode};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SessionError {
+    TruncatedPacket { expected: usize, actual: usize },
+    MessageCountMismatch { expected: u16, actual: u16 },
+    SessionMismatch { expected: [u8; 10], actual: [u8; 10] },
+    UnknownPacketType(u8),
+    InvalidSequenceNumber,
+    LoginRejected(u8),
+}
+
+impl fmt::Display for SessionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SessionError::TruncatedPacket { expected, actual } => write!(
+                f,
+                "Truncated session packet: expected {} bytes, got {}",
+                expected, actual
+            ),
+            SessionError::MessageCountMismatch { expected, actual } => write!(
+                f,
+                "Session packet message count mismatch: header says {}, found {}",
+                expected, actual
+            ),
+            SessionError::SessionMismatch { expected, actual } => write!(
+                f,
+                "Session mismatch: expected '{}', got '{}'",
+                String::from_utf8_lossy(expected),
+                String::from_utf8_lossy(actual)
+            ),
+            SessionError::UnknownPacketType(packet_type) => write!(f, "Unknown session packet type '{}'", *packet_type as char),
+            SessionError::InvalidSequenceNumber => write!(f, "Invalid session sequence number"),
+            SessionError::LoginRejected(reason) => write!(f, "Session login rejected with reason '{}'", *reason as char),
+        }
+    }
+}
+
+impl From<SessionError> for ErrorCode {
+    fn from(error: SessionError) -> Self {
+        ErrorCode::SessionInvalid(error)
+    }
+}
+
+// Receives the ITCH messages unwrapped from a session layer in sequence order
+pub trait SessionHandler {
+    fn on_message(&mut self, sequence: u64, message: &[u8]) -> Result<(), ErrorCode>;
+    // Messages in [expected, received) were never delivered
+    fn on_gap(&mut self, _expected: u64, _received: u64) -> Result<(), ErrorCode> {
+        Ok(())
+    }
+    // The message was already delivered and has been dropped
+    fn on_duplicate(&mut self, _sequence: u64) {}
+    fn on_end_of_session(&mut self) {}
+    // Handlers ordering messages themselves, like the Sequencer, are given every message
+    // as received, retransmissions included, and detect gaps and duplicates on their own
+    fn is_sequencing(&self) -> bool {
+        false
+    }
+    // Heartbeats and logins of a sequencing handler's session announce the next sequence number
+    fn on_next_sequence(&mut self, _next: u64) -> Result<(), ErrorCode> {
+        Ok(())
+    }
+}
+
+impl<H> SessionHandler for ITCHHandler<H>
+where
+    H: Handler,
+{
+    fn on_message(&mut self, _sequence: u64, message: &[u8]) -> Result<(), ErrorCode> {
+        self.replay_message(message)
+    }
+}
@@ -0,0 +1,236 @@
+use std::io::{self, Read};
+
+use crate::orders::order::ErrorCode;
+
+use super::{sequence_tracker::SequenceTracker, session_handler::{SessionError, SessionHandler}};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SoupBinTCPPacket<'a> {
+    Debug(&'a [u8]),
+    LoginAccepted { session: [u8; 10], sequence_number: u64 },
+    LoginRejected(u8),
+    SequencedData(&'a [u8]),
+    UnsequencedData(&'a [u8]),
+    ServerHeartbeat,
+    EndOfSession,
+    LoginRequest { username: [u8; 6], password: [u8; 10], session: [u8; 10], sequence_number: u64 },
+    LogoutRequest,
+    ClientHeartbeat,
+}
+
+impl<'a> SoupBinTCPPacket<'a> {
+    // Decodes a packet without its length prefix, starting at the packet type
+    pub fn decode(buffer: &'a [u8]) -> Result<Self, SessionError> {
+        let (&packet_type, payload) = buffer
+            .split_first()
+            .ok_or(SessionError::TruncatedPacket { expected: 1, actual: 0 })?;
+
+        let expect = |size: usize| {
+            if payload.len() < size {
+                Err(SessionError::TruncatedPacket { expected: size + 1, actual: buffer.len() })
+            } else {
+                Ok(())
+            }
+        };
+
+        match packet_type {
+            b'+' => Ok(SoupBinTCPPacket::Debug(payload)),
+            b'A' => {
+                expect(30)?;
+                Ok(SoupBinTCPPacket::LoginAccepted {
+                    session: alpha(&payload[0..10]),
+                    sequence_number: numeric(&payload[10..30])?,
+                })
+            }
+            b'J' => {
+                expect(1)?;
+                Ok(SoupBinTCPPacket::LoginRejected(payload[0]))
+            }
+            b'S' => Ok(SoupBinTCPPacket::SequencedData(payload)),
+            b'U' => Ok(SoupBinTCPPacket::UnsequencedData(payload)),
+            b'H' => Ok(SoupBinTCPPacket::ServerHeartbeat),
+            b'Z' => Ok(SoupBinTCPPacket::EndOfSession),
+            b'L' => {
+                expect(46)?;
+                Ok(SoupBinTCPPacket::LoginRequest {
+                    username: alpha(&payload[0..6]),
+                    password: alpha(&payload[6..16]),
+                    session: alpha(&payload[16..26]),
+                    sequence_number: numeric(&payload[26..46])?,
+                })
+            }
+            b'O' => Ok(SoupBinTCPPacket::LogoutRequest),
+            b'R' => Ok(SoupBinTCPPacket::ClientHeartbeat),
+            _ => Err(SessionError::UnknownPacketType(packet_type)),
+        }
+    }
+
+    // Encodes the packet including its length prefix
+    pub fn encode(&self) -> Vec<u8> {
+        let mut packet = vec![0, 0];
+        match self {
+            SoupBinTCPPacket::Debug(text) => {
+                packet.push(b'+');
+                packet.extend_from_slice(text);
+            }
+            SoupBinTCPPacket::LoginAccepted { session, sequence_number } => {
+                packet.push(b'A');
+                packet.extend_from_slice(session);
+                packet.extend_from_slice(format!("{:>20}", sequence_number).as_bytes());
+            }
+            SoupBinTCPPacket::LoginRejected(reason) => {
+                packet.push(b'J');
+                packet.push(*reason);
+            }
+            SoupBinTCPPacket::SequencedData(message) => {
+                packet.push(b'S');
+                packet.extend_from_slice(message);
+            }
+            SoupBinTCPPacket::UnsequencedData(message) => {
+                packet.push(b'U');
+                packet.extend_from_slice(message);
+            }
+            SoupBinTCPPacket::ServerHeartbeat => packet.push(b'H'),
+            SoupBinTCPPacket::EndOfSession => packet.push(b'Z'),
+            SoupBinTCPPacket::LoginRequest { username, password, session, sequence_number } => {
+                packet.push(b'L');
+                packet.extend_from_slice(username);
+                packet.extend_from_slice(password);
+                packet.extend_from_slice(session);
+                packet.extend_from_slice(format!("{:>20}", sequence_number).as_bytes());
+            }
+            SoupBinTCPPacket::LogoutRequest => packet.push(b'O'),
+            SoupBinTCPPacket::ClientHeartbeat => packet.push(b'R'),
+        }
+        let size = (packet.len() - 2) as u16;
+        packet[0..2].copy_from_slice(&size.to_be_bytes());
+        packet
+    }
+}
+
+fn alpha<const N: usize>(buffer: &[u8]) -> [u8; N] {
+    let mut value = [b' '; N];
+    value.copy_from_slice(&buffer[..N]);
+    value
+}
+
+// Numeric fields are ASCII, padded on the left with spaces
+fn numeric(buffer: &[u8]) -> Result<u64, SessionError> {
+    std::str::from_utf8(buffer)
+        .ok()
+        .and_then(|text| text.trim().parse().ok())
+        .ok_or(SessionError::InvalidSequenceNumber)
+}
+
+// Unwraps a SoupBinTCP byte stream into the ITCH message stream. Sequenced data
+// packets are numbered implicitly from the sequence number of the login.
+pub struct SoupBinTCPSession {
+    session: Option<[u8; 10]>,
+    sequence: u64,
+    tracker: SequenceTracker,
+    ended: bool,
+    cache: Vec<u8>,
+}
+
+impl Default for SoupBinTCPSession {
+    fn default() -> Self {
+        SoupBinTCPSession {
+            session: None,
+            sequence: 1,
+            tracker: SequenceTracker::default(),
+            ended: false,
+            cache: Vec::new(),
+        }
+    }
+}
+
+impl SoupBinTCPSession {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn session(&self) -> Option<[u8; 10]> {
+        self.session
+    }
+
+    pub fn expected_sequence_number(&self) -> Option<u64> {
+        self.tracker.expected()
+    }
+
+    pub fn is_ended(&self) -> bool {
+        self.ended
+    }
+
+    pub fn process<R: Read, H: SessionHandler>(&mut self, mut reader: R, handler: &mut H) -> io::Result<()> {
+        let mut buffer = [0; 8192];
+        while !self.ended {
+            let size = reader.read(&mut buffer)?;
+            if size == 0 {
+                break;
+            }
+            self.process_buffer(&buffer[..size], handler)?;
+        }
+        Ok(())
+    }
+
+    // Feeds a chunk of the stream. Packets split across chunk boundaries are
+    // accumulated in the cache until they are complete.
+    pub fn process_buffer<H: SessionHandler>(&mut self, data: &[u8], handler: &mut H) -> io::Result<()> {
+        let result = if self.cache.is_empty() {
+            let consumed = self.process_packets(data, handler);
+            consumed.map(|index| self.cache.extend_from_slice(&data[index..]))
+        } else {
+            let mut cache = std::mem::take(&mut self.cache);
+            cache.extend_from_slice(data);
+            let consumed = self.process_packets(&cache, handler);
+            if let Ok(index) = consumed {
+                cache.drain(..index);
+            }
+            self.cache = cache;
+            consumed.map(|_| ())
+        };
+        result.map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))
+    }
+
+    // Processes all complete packets and returns the number of bytes consumed
+    fn process_packets<H: SessionHandler>(&mut self, data: &[u8], handler: &mut H) -> Result<usize, ErrorCode> {
+        let mut index = 0;
+        while !self.ended && index + 2 <= data.len() {
+            let size = u16::from_be_bytes([data[index], data[index + 1]]) as usize;
+            if index + 2 + size > data.len() {
+                break;
+            }
+            self.process_packet(&data[index + 2..index + 2 + size], handler)?;
+            index += 2 + size;
+        }
+        Ok(index)
+    }
+
+    pub fn process_packet<H: SessionHandler>(&mut self, buffer: &[u8], handler: &mut H) -> Result<(), ErrorCode> {
+        match SoupBinTCPPacket::decode(buffer)? {
+            SoupBinTCPPacket::LoginAccepted { session, sequence_number } => {
+                self.session = Some(session);
+                self.sequence = sequence_number;
+                // Keep the delivered position so a replay from an earlier sequence number reports duplicates
+                self.tracker.advance_to(sequence_number, handler)?;
+                Ok(())
+            }
+            SoupBinTCPPacket::LoginRejected(reason) => Err(SessionError::LoginRejected(reason).into()),
+            SoupBinTCPPacket::SequencedData(message) => {
+                let sequence = self.sequence;
+                self.sequence += 1;
+                if self.tracker.accept(sequence, handler)? {
+                    handler.on_message(sequence, message)?;
+                }
+                Ok(())
+            }
+            SoupBinTCPPacket::EndOfSession => {
+                self.ended = true;
+                handler.on_end_of_session();
+                Ok(())
+            }
+            // Heartbeats, debug text and unsequenced or client packets carry no ITCH data
+            _ => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,2 @@
+pub mod symbol;
+pub mod symbol_registry;
@@ -0,0 +1,114 @@
+use crate::itch_handler::StockDirectoryView;
+
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub enum MarketCategory {
+    NasdaqGlobalSelect,
+    NasdaqGlobalMarket,
+    NasdaqCapitalMarket,
+    NYSE,
+    NYSEAmerican,
+    NYSEArca,
+    BATS,
+    InvestorsExchange,
+    #[default]
+    NotAvailable,
+}
+
+impl From<u8> for MarketCategory {
+    fn from(code: u8) -> Self {
+        match code {
+            b'Q' => MarketCategory::NasdaqGlobalSelect,
+            b'G' => MarketCategory::NasdaqGlobalMarket,
+            b'S' => MarketCategory::NasdaqCapitalMarket,
+            b'N' => MarketCategory::NYSE,
+            b'A' => MarketCategory::NYSEAmerican,
+            b'P' => MarketCategory::NYSEArca,
+            b'Z' => MarketCategory::BATS,
+            b'V' => MarketCategory::InvestorsExchange,
+            _ => MarketCategory::NotAvailable,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub enum FinancialStatus {
+    Normal,
+    Deficient,
+    Delinquent,
+    Bankrupt,
+    Suspended,
+    DeficientBankrupt,
+    DeficientDelinquent,
+    DelinquentBankrupt,
+    DeficientDelinquentBankrupt,
+    CreationsRedemptionsSuspended,
+    #[default]
+    NotAvailable,
+}
+
+impl From<u8> for FinancialStatus {
+    fn from(code: u8) -> Self {
+        match code {
+            b'N' => FinancialStatus::Normal,
+            b'D' => FinancialStatus::Deficient,
+            b'E' => FinancialStatus::Delinquent,
+            b'Q' => FinancialStatus::Bankrupt,
+            b'S' => FinancialStatus::Suspended,
+            b'G' => FinancialStatus::DeficientBankrupt,
+            b'H' => FinancialStatus::DeficientDelinquent,
+            b'J' => FinancialStatus::DelinquentBankrupt,
+            b'K' => FinancialStatus::DeficientDelinquentBankrupt,
+            b'C' => FinancialStatus::CreationsRedemptionsSuspended,
+            _ => FinancialStatus::NotAvailable,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Symbol {
+    pub id: u64,
+    pub name: [u8; 8],
+    pub market_category: MarketCategory,
+    pub financial_status: FinancialStatus,
+    pub round_lot_size: u32,
+    pub round_lots_only: bool,
+    pub etp: bool,
+    pub etp_leverage_factor: u32,
+    pub inverse: bool,
+}
+
+impl Symbol {
+    pub fn new(id: u64, name: &str) -> Self {
+        let mut padded = [b' '; 8];
+        let length = std::cmp::min(name.len(), padded.len());
+        padded[..length].copy_from_slice(&name.as_bytes()[..length]);
+        Symbol {
+            id,
+            name: padded,
+            ..Default::default()
+        }
+    }
+
+    // ITCH pads tickers with trailing spaces up to eight characters
+    pub fn ticker(&self) -> &str {
+        std::str::from_utf8(&self.name)
+            .unwrap_or_default()
+            .trim_end()
+    }
+}
+
+impl From<&StockDirectoryView<'_>> for Symbol {
+    fn from(message: &StockDirectoryView<'_>) -> Self {
+        Symbol {
+            id: message.stock_locate() as u64,
+            name: *message.stock(),
+            market_category: MarketCategory::from(message.market_category()),
+            financial_status: FinancialStatus::from(message.financial_status_indicator()),
+            round_lot_size: message.round_lot_size(),
+            round_lots_only: message.round_lots_only() == b'Y',
+            etp: message.etp_flag() == b'Y',
+            etp_leverage_factor: message.etp_leverage_factor(),
+            inverse: message.inverse_indicator() == b'Y',
+        }
+    }
+}
@@ -0,0 +1,51 @@
+use std::collections::HashMap;
+
+use crate::orders::order::ErrorCode;
+
+use super::symbol::Symbol;
+
+pub trait SymbolOps
+{
+    fn add_symbol(&mut self, symbol: Symbol) -> Result<(), ErrorCode>;
+    fn get_symbol(&self, id: u64) -> Result<&Symbol, ErrorCode>;
+    fn get_symbol_by_ticker(&self, ticker: &str) -> Result<&Symbol, ErrorCode>;
+    fn remove_symbol(&mut self, id: u64) -> Result<Symbol, ErrorCode>;
+}
+
+// Symbols keyed by ITCH stock locate, with a reverse index by ticker
+#[derive(Default)]
+pub struct SymbolRegistry
+{
+    symbols: HashMap<u64, Symbol>,
+    tickers: HashMap<[u8; 8], u64>,
+}
+
+impl SymbolOps for SymbolRegistry
+{
+    fn add_symbol(&mut self, symbol: Symbol) -> Result<(), ErrorCode> {
+        if self.symbols.contains_key(&symbol.id) || self.tickers.contains_key(&symbol.name) {
+            return Err(ErrorCode::SymbolDuplicate);
+        }
+        self.tickers.insert(symbol.name, symbol.id);
+        self.symbols.insert(symbol.id, symbol);
+        Ok(())
+    }
+
+    fn get_symbol(&self, id: u64) -> Result<&Symbol, ErrorCode> {
+        self.symbols.get(&id).ok_or(ErrorCode::SymbolNotFound)
+    }
+
+    fn get_symbol_by_ticker(&self, ticker: &str) -> Result<&Symbol, ErrorCode> {
+        let name = Symbol::new(0, ticker).name;
+        self.tickers
+            .get(&name)
+            .and_then(|id| self.symbols.get(id))
+            .ok_or(ErrorCode::SymbolNotFound)
+    }
+
+    fn remove_symbol(&mut self, id: u64) -> Result<Symbol, ErrorCode> {
+        let symbol = self.symbols.remove(&id).ok_or(ErrorCode::SymbolNotFound)?;
+        self.tickers.remove(&symbol.name);
+        Ok(symbol)
+    }
+}
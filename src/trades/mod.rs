@@ -0,0 +1 @@
+pub mod trade_tape;
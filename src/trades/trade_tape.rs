@@ -0,0 +1,168 @@
+use std::{collections::HashMap, ops::Range};
+
+use crate::{itch_handler::{CrossTradeView, TradeView}, order_book::imbalance::CrossType, orders::order::{Order, OrderSide}};
+
+// Single print of the time and sales
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Trade {
+    pub symbol_id: u64,
+    pub match_number: u64,
+    pub price: u64,
+    pub quantity: u64,
+    // Unknown for crosses, which have no aggressor
+    pub aggressor: Option<OrderSide>,
+    // Non-printable executions are left out of the volume and the last sale price
+    pub printable: bool,
+    // Set for opening, closing, halt, IPO and intraday crosses ('Q')
+    pub cross_type: Option<CrossType>,
+    pub timestamp: u64,
+    // Busted by a Broken Trade ('B') message, the print stays on the tape flagged
+    pub broken: bool,
+}
+
+impl Trade {
+    // Execution of a resting order ('E' and 'C'), hit by an aggressor on the other side
+    pub fn execution(order: &Order, match_number: u64, price: u64, quantity: u64, printable: bool, timestamp: u64) -> Self {
+        Trade {
+            symbol_id: order.symbol_id,
+            match_number,
+            price,
+            quantity,
+            aggressor: Some(opposite(order.order_side)),
+            printable,
+            cross_type: None,
+            timestamp,
+            broken: false,
+        }
+    }
+}
+
+fn opposite(side: OrderSide) -> OrderSide {
+    match side {
+        OrderSide::Buy => OrderSide::Sell,
+        OrderSide::Sell => OrderSide::Buy,
+    }
+}
+
+// Executions of non-displayed orders ('P'). The side is the one of the resting order.
+impl From<&TradeView<'_>> for Trade {
+    fn from(message: &TradeView<'_>) -> Self {
+        let resting = match message.buy_sell_indicator() {
+            b'S' => OrderSide::Sell,
+            _ => OrderSide::Buy,
+        };
+        Trade {
+            symbol_id: message.stock_locate() as u64,
+            match_number: message.match_number(),
+            price: message.price() as u64,
+            quantity: message.shares() as u64,
+            aggressor: Some(opposite(resting)),
+            printable: true,
+            cross_type: None,
+            timestamp: message.timestamp(),
+            broken: false,
+        }
+    }
+}
+
+impl From<&CrossTradeView<'_>> for Trade {
+    fn from(message: &CrossTradeView<'_>) -> Self {
+        Trade {
+            symbol_id: message.stock_locate() as u64,
+            match_number: message.match_number(),
+            price: message.cross_price() as u64,
+            quantity: message.shares(),
+            aggressor: None,
+            printable: true,
+            cross_type: Some(CrossType::from(message.cross_type())),
+            timestamp: message.timestamp(),
+            broken: false,
+        }
+    }
+}
+
+// Time and sales of every symbol, each kept in timestamp order. Prints are indexed by
+// match number, so broken trades can be found again.
+#[derive(Debug, Default)]
+pub struct TradeTape {
+    trades: HashMap<u64, Vec<Trade>>,
+    // Symbol id and timestamp of every print by match number
+    matches: HashMap<u64, (u64, u64)>,
+}
+
+impl TradeTape {
+    pub fn new() -> Self {
+        TradeTape::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.trades.values().map(Vec::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.trades.values().all(Vec::is_empty)
+    }
+
+    pub fn record(&mut self, trade: Trade) {
+        self.matches.insert(trade.match_number, (trade.symbol_id, trade.timestamp));
+        let trades = self.trades.entry(trade.symbol_id).or_default();
+
+        // Keep the tape sorted even if a print arrives out of order
+        let position = trades.partition_point(|recorded| recorded.timestamp <= trade.timestamp);
+        trades.insert(position, trade);
+    }
+
+    pub fn find(&self, match_number: u64) -> Option<&Trade> {
+        let (symbol_id, position) = self.position(match_number)?;
+        Some(&self.trades[&symbol_id][position])
+    }
+
+    // Flags the print of the match number as broken and returns it. Unknown and
+    // already broken prints are left alone.
+    pub fn break_trade(&mut self, match_number: u64) -> Option<Trade> {
+        let (symbol_id, position) = self.position(match_number)?;
+        let trade = self.trades.get_mut(&symbol_id)?.get_mut(position)?;
+        if trade.broken {
+            return None;
+        }
+        trade.broken = true;
+        Some(*trade)
+    }
+
+    // Price of the latest printable trade of the symbol still standing
+    pub fn last_price(&self, symbol_id: u64) -> Option<u64> {
+        self.trades(symbol_id)
+            .iter()
+            .rev()
+            .find(|trade| trade.printable && !trade.broken)
+            .map(|trade| trade.price)
+    }
+
+    // Latest trade of the symbol still standing, printable or not
+    pub fn last_trade(&self, symbol_id: u64) -> Option<&Trade> {
+        self.trades(symbol_id).iter().rev().find(|trade| !trade.broken)
+    }
+
+    pub fn trades(&self, symbol_id: u64) -> &[Trade] {
+        self.trades.get(&symbol_id).map_or(&[], Vec::as_slice)
+    }
+
+    // Trades of the symbol printed within the given timestamp range
+    pub fn trades_between(&self, symbol_id: u64, range: Range<u64>) -> &[Trade] {
+        let trades = self.trades(symbol_id);
+        let start = trades.partition_point(|trade| trade.timestamp < range.start);
+        let end = trades.partition_point(|trade| trade.timestamp < range.end);
+        &trades[start..std::cmp::max(start, end)]
+    }
+
+    fn position(&self, match_number: u64) -> Option<(u64, usize)> {
+        let &(symbol_id, timestamp) = self.matches.get(&match_number)?;
+        let trades = self.trades(symbol_id);
+        let start = trades.partition_point(|trade| trade.timestamp < timestamp);
+        let position = trades[start..]
+            .iter()
+            .take_while(|trade| trade.timestamp == timestamp)
+            .position(|trade| trade.match_number == match_number)?;
+        Some((symbol_id, start + position))
+    }
+}
@@ -0,0 +1,103 @@
+mod common;
+
+use common::*;
+use itch_plus::{
+    market_executors::order_book_operations::OrderBookContainer,
+    order_book::{auction_book::AuctionType, trading_state::{TradingState, TradingStatus}},
+    orders::order::{OrderSide, TimeInForce},
+};
+
+#[test]
+fn auction_orders_wait_for_the_cross() {
+    let mut manager = manager();
+    manager.add_order(limit(10, OrderSide::Sell, 1000, 50)).unwrap();
+    manager.add_order(with_time_in_force(limit(1, OrderSide::Buy, 1010, 100), TimeInForce::OPG)).unwrap();
+
+    // The opening order takes no part in continuous matching
+    assert_eq!(manager.handler.execute_orders(), 0);
+    assert_eq!(manager.get_order(10).unwrap().leaves_quantity, 50);
+    assert_eq!(manager.get_order_book(SYMBOL).unwrap().opening_auction.len(), 1);
+}
+
+#[test]
+fn auction_orders_trade_with_each_other() {
+    let mut manager = manager();
+    manager.add_order(with_time_in_force(limit(1, OrderSide::Buy, 1010, 100), TimeInForce::OPG)).unwrap();
+    manager.add_order(with_time_in_force(limit(2, OrderSide::Sell, 1000, 100), TimeInForce::OPG)).unwrap();
+
+    manager.cross_auction(SYMBOL, AuctionType::Opening).unwrap();
+
+    assert_eq!(manager.handler.execute_orders(), 2);
+    assert!(manager.get_order(1).is_err());
+    assert!(manager.get_order(2).is_err());
+    assert!(manager.get_order_book(SYMBOL).unwrap().opening_auction.is_empty());
+}
+
+#[test]
+fn uncross_takes_the_book_and_cancels_the_leftover() {
+    let mut manager = manager();
+    manager.add_order(limit(10, OrderSide::Sell, 1005, 50)).unwrap();
+    manager.add_order(with_time_in_force(limit(1, OrderSide::Buy, 1010, 100), TimeInForce::OPG)).unwrap();
+    manager.add_order(with_time_in_force(limit(2, OrderSide::Sell, 1000, 60), TimeInForce::OPG)).unwrap();
+    manager.add_order(with_time_in_force(limit(3, OrderSide::Sell, 1020, 30), TimeInForce::OPG)).unwrap();
+
+    manager.cross_auction(SYMBOL, AuctionType::Opening).unwrap();
+
+    // 60 from the auction sell and 40 from the resting ask fill the auction buy
+    assert_eq!(manager.handler.execute_orders(), 4);
+    assert!(manager.get_order(1).is_err());
+    assert_eq!(manager.get_order(10).unwrap().leaves_quantity, 10);
+
+    // The sell priced away from the cross is cancelled, not queued
+    assert!(manager.get_order(3).is_err());
+}
+
+#[test]
+fn uncross_skips_all_or_none_orders_it_cannot_fill() {
+    let mut manager = manager();
+    manager.add_order(with_time_in_force(limit(10, OrderSide::Sell, 1000, 50), TimeInForce::AON)).unwrap();
+    manager.add_order(limit(11, OrderSide::Sell, 1000, 20)).unwrap();
+    manager.add_order(with_time_in_force(limit(1, OrderSide::Buy, 1010, 20), TimeInForce::OPG)).unwrap();
+
+    manager.cross_auction(SYMBOL, AuctionType::Opening).unwrap();
+
+    // The auction buy trades with the ask queued behind the larger 'All-Or-None' one
+    assert!(manager.get_order(1).is_err() && manager.get_order(11).is_err());
+    assert_eq!(manager.get_order(10).unwrap().leaves_quantity, 50);
+    assert_eq!(manager.handler.execute_orders(), 2);
+}
+
+#[test]
+fn uncross_trades_hidden_iceberg_reserves() {
+    let mut manager = manager();
+    manager.add_order(iceberg(10, OrderSide::Sell, 1000, 30, 10)).unwrap();
+    manager.add_order(with_time_in_force(limit(1, OrderSide::Buy, 1010, 30), TimeInForce::OPG)).unwrap();
+
+    manager.cross_auction(SYMBOL, AuctionType::Opening).unwrap();
+
+    // The reserve is replenished slice by slice until the auction buy is filled
+    assert!(manager.get_order(1).is_err() && manager.get_order(10).is_err());
+    assert_eq!(manager.handler.execute_orders(), 6);
+    assert!(manager.get_order_book(SYMBOL).unwrap().best_ask().is_none());
+}
+
+#[test]
+fn halted_books_keep_their_auction_orders() {
+    let mut manager = manager();
+    manager.add_order(with_time_in_force(limit(1, OrderSide::Buy, 1010, 100), TimeInForce::OPG)).unwrap();
+    manager.add_order(with_time_in_force(limit(2, OrderSide::Sell, 1000, 100), TimeInForce::OPG)).unwrap();
+
+    let halted = TradingState { status: TradingStatus::Halted, ..Default::default() };
+    manager.order_books.get_order_book(&SYMBOL).unwrap().update_trading_state(halted);
+    manager.cross_auction(SYMBOL, AuctionType::Opening).unwrap();
+
+    // Nothing trades or gets cancelled while the book is halted
+    assert_eq!(manager.handler.execute_orders(), 0);
+    assert!(manager.get_order(1).is_ok() && manager.get_order(2).is_ok());
+    assert_eq!(manager.get_order_book(SYMBOL).unwrap().opening_auction.len(), 2);
+
+    // The orders cross once trading resumes
+    manager.order_books.get_order_book(&SYMBOL).unwrap().update_trading_state(TradingState::default());
+    manager.cross_auction(SYMBOL, AuctionType::Opening).unwrap();
+    assert_eq!(manager.handler.execute_orders(), 2);
+}
@@ -0,0 +1,113 @@
+#![allow(dead_code)]
+
+use itch_plus::{
+    market_executors::market_manager::MarketManager,
+    market_handler::MarketHandler,
+    orders::order::{Order, OrderSide, TimeInForce},
+    symbols::symbol::Symbol,
+};
+
+pub const SYMBOL: u64 = 1;
+
+// Market manager with a single matching order book for SYMBOL
+pub fn manager() -> MarketManager<MarketHandler> {
+    let mut manager = MarketManager::new(MarketHandler::new());
+    let symbol = Symbol::new(SYMBOL, "TEST");
+    manager.add_symbol(symbol.clone()).unwrap();
+    manager.add_order_book(&symbol).unwrap();
+    manager.enable_matching().unwrap();
+    manager
+}
+
+pub fn limit(id: u64, side: OrderSide, price: u64, quantity: u64) -> Order {
+    Order::limit(id, SYMBOL, side, price, quantity)
+}
+
+pub fn add_asks(manager: &mut MarketManager<MarketHandler>, levels: &[(u64, u64, u64)]) {
+    for &(id, price, quantity) in levels {
+        manager.add_order(limit(id, OrderSide::Sell, price, quantity)).unwrap();
+    }
+}
+
+pub fn with_time_in_force(mut order: Order, time_in_force: TimeInForce) -> Order {
+    order.time_in_force = time_in_force;
+    order
+}
+
+pub fn iceberg(id: u64, side: OrderSide, price: u64, quantity: u64, max_visible_quantity: u64) -> Order {
+    let mut order = limit(id, side, price, quantity);
+    order.max_visible_quantity = max_visible_quantity;
+    order
+}
+
+// Builders of raw ITCH 5.0 messages shared by the integration tests
+
+pub fn header(message_type: u8, stock_locate: u16, timestamp: u64) -> Vec<u8> {
+    let mut message = vec![message_type];
+    message.extend_from_slice(&stock_locate.to_be_bytes());
+    message.extend_from_slice(&[0, 0]);
+    message.extend_from_slice(&timestamp.to_be_bytes()[2..]);
+    message
+}
+
+pub fn stock_directory(stock_locate: u16, stock: &[u8; 8]) -> Vec<u8> {
+    let mut message = header(b'R', stock_locate, 0);
+    message.extend_from_slice(stock);
+    // Nasdaq Global Select, normal financial status, round lots of 100
+    message.extend_from_slice(b"QN");
+    message.extend_from_slice(&100u32.to_be_bytes());
+    message.extend_from_slice(b"NCZ PN 1N");
+    message.extend_from_slice(&0u32.to_be_bytes());
+    message.push(b'N');
+    message
+}
+
+pub fn add_order(stock_locate: u16, timestamp: u64, id: u64, side: u8, shares: u32, stock: &[u8; 8], price: u32) -> Vec<u8> {
+    let mut message = header(b'A', stock_locate, timestamp);
+    message.extend_from_slice(&id.to_be_bytes());
+    message.push(side);
+    message.extend_from_slice(&shares.to_be_bytes());
+    message.extend_from_slice(stock);
+    message.extend_from_slice(&price.to_be_bytes());
+    message
+}
+
+pub fn order_executed(stock_locate: u16, timestamp: u64, id: u64, shares: u32, match_number: u64) -> Vec<u8> {
+    let mut message = header(b'E', stock_locate, timestamp);
+    message.extend_from_slice(&id.to_be_bytes());
+    message.extend_from_slice(&shares.to_be_bytes());
+    message.extend_from_slice(&match_number.to_be_bytes());
+    message
+}
+
+pub fn order_delete(stock_locate: u16, timestamp: u64, id: u64) -> Vec<u8> {
+    let mut message = header(b'D', stock_locate, timestamp);
+    message.extend_from_slice(&id.to_be_bytes());
+    message
+}
+
+pub fn order_executed_with_price(stock_locate: u16, timestamp: u64, id: u64, shares: u32, match_number: u64, price: u32) -> Vec<u8> {
+    let mut message = header(b'C', stock_locate, timestamp);
+    message.extend_from_slice(&id.to_be_bytes());
+    message.extend_from_slice(&shares.to_be_bytes());
+    message.extend_from_slice(&match_number.to_be_bytes());
+    message.push(b'Y');
+    message.extend_from_slice(&price.to_be_bytes());
+    message
+}
+
+pub fn broken_trade(stock_locate: u16, timestamp: u64, match_number: u64) -> Vec<u8> {
+    let mut message = header(b'B', stock_locate, timestamp);
+    message.extend_from_slice(&match_number.to_be_bytes());
+    message
+}
+
+// Length-prefixed framing of a binary ITCH 5.0 file
+pub fn frame(messages: &[Vec<u8>]) -> Vec<u8> {
+    let mut data = Vec::new();
+    for message in messages {
+        data.extend_from_slice(&(message.len() as u16).to_be_bytes());
+        data.extend_from_slice(message);
+    }
+    data
+}
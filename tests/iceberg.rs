@@ -0,0 +1,55 @@
+mod common;
+
+use common::*;
+use itch_plus::orders::order::{ErrorCode, OrderSide, TimeInForce};
+
+#[test]
+fn iceberg_displays_one_slice_at_a_time() {
+    let mut manager = manager();
+    manager.add_order(iceberg(1, OrderSide::Sell, 100, 30, 10)).unwrap();
+
+    let order = manager.get_order(1).unwrap();
+    assert_eq!((order.visible_quantity(), order.hidden_quantity()), (10, 20));
+}
+
+#[test]
+fn iceberg_without_a_displayed_slice_is_rejected() {
+    let mut manager = manager();
+    assert!(matches!(manager.add_order(iceberg(1, OrderSide::Sell, 100, 30, 0)), Err(ErrorCode::OrderParameterInvalid)));
+    assert!(manager.get_order(1).is_err());
+}
+
+#[test]
+fn replenished_iceberg_loses_time_priority() {
+    let mut manager = manager();
+    manager.add_order(iceberg(1, OrderSide::Sell, 100, 30, 10)).unwrap();
+    manager.add_order(limit(2, OrderSide::Sell, 100, 10)).unwrap();
+
+    // Only the displayed slice trades before the refreshed slice goes to the back
+    manager.add_order(limit(10, OrderSide::Buy, 100, 15)).unwrap();
+    let order = manager.get_order(1).unwrap();
+    assert_eq!((order.leaves_quantity, order.visible_quantity()), (20, 10));
+    assert_eq!(manager.get_order(2).unwrap().leaves_quantity, 5);
+
+    manager.add_order(limit(11, OrderSide::Buy, 100, 5)).unwrap();
+    assert!(manager.get_order(2).is_err());
+    assert_eq!(manager.get_order(1).unwrap().leaves_quantity, 20);
+}
+
+#[test]
+fn fill_or_kill_chain_counts_hidden_reserves() {
+    let mut manager = manager();
+    manager.add_order(iceberg(1, OrderSide::Sell, 100, 30, 10)).unwrap();
+    manager.add_order(limit(2, OrderSide::Sell, 101, 10)).unwrap();
+
+    // The reserve is replenished slice by slice while the chain is executed
+    manager.add_order(with_time_in_force(limit(10, OrderSide::Buy, 100, 25), TimeInForce::FOK)).unwrap();
+    assert!(manager.get_order(10).is_err());
+    let order = manager.get_order(1).unwrap();
+    assert_eq!((order.leaves_quantity, order.visible_quantity()), (5, 5));
+
+    // The chain goes on to the next level only once the reserve is used up
+    manager.add_order(with_time_in_force(limit(11, OrderSide::Buy, 101, 10), TimeInForce::FOK)).unwrap();
+    assert!(manager.get_order(11).is_err() && manager.get_order(1).is_err());
+    assert_eq!(manager.get_order(2).unwrap().leaves_quantity, 5);
+}
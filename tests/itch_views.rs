@@ -0,0 +1,59 @@
+mod common;
+
+use common::*;
+use itch_plus::itch_handler::{AddOrderView, ITCHError, ITCHMessage, StockDirectoryView};
+
+const STOCK: &[u8; 8] = b"AAPL    ";
+
+// Message sizes of the Nasdaq TotalView-ITCH 5.0 specification
+const SIZES: [(u8, usize); 23] = [
+    (b'S', 12), (b'R', 39), (b'H', 25), (b'Y', 20), (b'L', 26), (b'V', 35), (b'W', 12), (b'K', 28),
+    (b'J', 35), (b'h', 21), (b'A', 36), (b'F', 40), (b'E', 31), (b'C', 36), (b'X', 23), (b'D', 19),
+    (b'U', 35), (b'P', 44), (b'Q', 40), (b'B', 19), (b'I', 50), (b'N', 20), (b'O', 48),
+];
+
+#[test]
+fn views_accept_only_their_specified_size() {
+    for (message_type, size) in SIZES {
+        let mut buffer = vec![0; size + 1];
+        buffer[0] = message_type;
+
+        let message = ITCHMessage::decode(&buffer[..size]).unwrap();
+        assert_eq!(message.as_bytes().len(), size);
+
+        for actual in [size - 1, size + 1] {
+            assert_eq!(
+                ITCHMessage::decode(&buffer[..actual]),
+                Err(ITCHError::InvalidMessageSize { message_type, expected: size, actual })
+            );
+        }
+    }
+}
+
+#[test]
+fn decode_rejects_empty_and_unknown_messages() {
+    assert_eq!(ITCHMessage::decode(&[]), Err(ITCHError::EmptyBuffer));
+    assert_eq!(ITCHMessage::decode(&[b'Z'; 12]), Err(ITCHError::UnknownMessageType(b'Z')));
+
+    // A view only wraps its own message type
+    let message = stock_directory(1, STOCK);
+    assert_eq!(AddOrderView::new(&message), Err(ITCHError::UnknownMessageType(b'R')));
+    assert!(StockDirectoryView::new(&message).is_ok());
+}
+
+#[test]
+fn views_read_big_endian_fields_in_place() {
+    let message = add_order(7, 0x0102_0304_0506, 42, b'S', 300, STOCK, 1_234_500);
+    let ITCHMessage::AddOrder(view) = ITCHMessage::decode(&message).unwrap() else {
+        panic!("expected an add order message");
+    };
+
+    assert_eq!(view.stock_locate(), 7);
+    assert_eq!(view.timestamp(), 0x0102_0304_0506);
+    assert_eq!(view.order_reference_number(), 42);
+    assert_eq!(view.buy_sell_indicator(), b'S');
+    assert_eq!(view.shares(), 300);
+    assert_eq!(view.stock(), STOCK);
+    assert_eq!(view.price(), 1_234_500);
+    assert_eq!(view.as_bytes().as_ptr(), message.as_ptr());
+}
@@ -0,0 +1,91 @@
+use itch_plus::levels::{indexing::{LevelHandle, LevelIndex, LevelTree}, level::{Level, LevelType}};
+
+// Upper bound of the AVL tree depth with n levels
+fn max_depth(len: usize) -> usize {
+    (1.4405 * ((len + 2) as f64).log2() - 0.3277).floor() as usize
+}
+
+fn prices(tree: &LevelTree) -> Vec<u64> {
+    tree.iter().map(|level| level.price).collect()
+}
+
+fn insert(tree: &mut LevelTree, price: u64) -> LevelHandle {
+    tree.insert(Level::with_price(tree.level_type(), price)).unwrap()
+}
+
+#[test]
+fn sorted_inserts_stay_balanced() {
+    let mut tree = LevelTree::new(LevelType::Ask);
+    for price in 1..=1_000 {
+        insert(&mut tree, price);
+        assert!(tree.depth() <= max_depth(tree.len()));
+    }
+
+    assert_eq!(tree.len(), 1_000);
+    assert_eq!(prices(&tree), (1..=1_000).collect::<Vec<u64>>());
+    assert!(tree.insert(Level::with_price(LevelType::Ask, 500)).is_err());
+}
+
+#[test]
+fn bid_levels_iterate_from_the_highest_price() {
+    let mut tree = LevelTree::new(LevelType::Bid);
+    for price in [5, 1, 9, 3, 7] {
+        insert(&mut tree, price);
+    }
+
+    assert_eq!(prices(&tree), vec![9, 7, 5, 3, 1]);
+    assert_eq!(tree.get(tree.best().unwrap()).unwrap().price, 9);
+}
+
+#[test]
+fn removals_keep_the_tree_balanced_and_handles_valid() {
+    let mut tree = LevelTree::new(LevelType::Ask);
+    let mut handles = Vec::new();
+
+    // Pseudo random prices from a linear congruential generator
+    let mut seed = 42u64;
+    let mut next = || {
+        seed = seed.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1_442_695_040_888_963_407);
+        seed >> 33
+    };
+    while handles.len() < 2_000 {
+        let price = next() % 100_000;
+        if tree.find(price).is_none() {
+            handles.push((price, insert(&mut tree, price)));
+        }
+    }
+
+    // Remove two levels out of three, interleaved with new inserts
+    let mut remaining = Vec::new();
+    for (index, (price, handle)) in handles.into_iter().enumerate() {
+        if index % 3 == 0 {
+            remaining.push((price, handle));
+            continue;
+        }
+        assert_eq!(tree.remove(handle).unwrap().price, price);
+        assert!(tree.find(price).is_none());
+        assert!(tree.depth() <= max_depth(tree.len()));
+    }
+    for price in 100_000..100_500 {
+        remaining.push((price, insert(&mut tree, price)));
+        assert!(tree.depth() <= max_depth(tree.len()));
+    }
+
+    // The surviving levels were never moved
+    assert_eq!(tree.len(), remaining.len());
+    for &(price, handle) in &remaining {
+        assert_eq!(tree.get(handle).unwrap().price, price);
+        assert_eq!(tree.find(price), Some(handle));
+    }
+
+    let mut expected: Vec<u64> = remaining.iter().map(|&(price, _)| price).collect();
+    expected.sort_unstable();
+    assert_eq!(prices(&tree), expected);
+
+    // Removed handles are not reused
+    for (_, handle) in remaining {
+        tree.remove(handle).unwrap();
+        assert!(tree.remove(handle).is_err());
+    }
+    assert!(tree.is_empty() && tree.depth() == 0);
+}
@@ -0,0 +1,179 @@
+mod common;
+
+use common::*;
+use itch_plus::{
+    market_executors::market_manager::MarketManager,
+    market_handler::{ExecutionEvent, Handler, LevelEvent, MarketHandler, OrderEvent},
+    order_book::{imbalance::Imbalance, order_book::OrderBook, reference_data::{CircuitBreaker, ReferenceUpdate}, trading_state::TradingState},
+    orders::order::{ErrorCode, Order, OrderSide, OrderType, TimeInForce},
+    symbols::symbol::Symbol,
+    trades::trade_tape::Trade,
+};
+
+fn market(id: u64, side: OrderSide, quantity: u64, slippage: u64) -> Order {
+    Order {
+        id,
+        symbol_id: SYMBOL,
+        order_type: OrderType::Market,
+        order_side: side,
+        quantity,
+        leaves_quantity: quantity,
+        visible_quantity: quantity,
+        time_in_force: TimeInForce::IOC,
+        slippage,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn market_order_without_liquidity_is_rejected() {
+    let mut manager = manager();
+    manager.add_order(limit(1, OrderSide::Buy, 100, 10)).unwrap();
+
+    assert!(matches!(manager.add_order(market(10, OrderSide::Buy, 10, 0)), Err(ErrorCode::NoLiquidity)));
+    assert_eq!(manager.handler.execute_orders(), 0);
+}
+
+#[test]
+fn market_order_stops_at_the_slippage_bound() {
+    let mut manager = manager();
+    add_asks(&mut manager, &[(1, 100, 10), (2, 101, 10), (3, 105, 10)]);
+
+    // Slippage of 2 ticks from the best ask caps the sweep at 102
+    manager.add_order(market(10, OrderSide::Buy, 30, 2)).unwrap();
+    assert!(manager.get_order(10).is_err());
+    assert!(manager.get_order(1).is_err() && manager.get_order(2).is_err());
+    assert_eq!(manager.get_order(3).unwrap().leaves_quantity, 10);
+
+    // Only the two resting orders inside the bound are executed
+    assert_eq!(manager.handler.execute_orders(), 2);
+}
+
+#[test]
+fn executions_track_the_average_fill_price() {
+    let mut manager = manager();
+    add_asks(&mut manager, &[(1, 100, 10), (2, 102, 30), (3, 103, 10)]);
+
+    manager.add_order(limit(10, OrderSide::Buy, 102, 50)).unwrap();
+
+    let order = manager.get_order(10).unwrap();
+    assert_eq!(order.executed_value, 100 * 10 + 102 * 30);
+    assert_eq!(order.average_fill_price(), Some(101));
+    assert_eq!(manager.get_order(3).unwrap().average_fill_price(), None);
+}
+
+// Market handler recording the average fill price of every deleted order as
+// (order Id, average fill price, unmatched)
+#[derive(Default)]
+struct Fills {
+    handler: MarketHandler,
+    deleted: Vec<(u64, Option<u64>, bool)>,
+}
+
+impl Handler for Fills {
+    fn on_add_symbol(&mut self, symbol: &Symbol) {
+        self.handler.on_add_symbol(symbol)
+    }
+
+    fn on_delete_symbol(&mut self, symbol: &Symbol) {
+        self.handler.on_delete_symbol(symbol)
+    }
+
+    fn on_add_order_book(&mut self, order_book: &OrderBook) {
+        self.handler.on_add_order_book(order_book)
+    }
+
+    fn on_update_order_book(&mut self, order_book: &OrderBook, top: bool) {
+        self.handler.on_update_order_book(order_book, top)
+    }
+
+    fn on_delete_order_book(&mut self, order_book: &OrderBook) {
+        self.handler.on_delete_order_book(order_book)
+    }
+
+    fn on_add_level(&mut self, event: LevelEvent) {
+        self.handler.on_add_level(event)
+    }
+
+    fn on_update_level(&mut self, event: LevelEvent) {
+        self.handler.on_update_level(event)
+    }
+
+    fn on_delete_level(&mut self, event: LevelEvent) {
+        self.handler.on_delete_level(event)
+    }
+
+    fn on_add_order(&mut self, event: OrderEvent) {
+        self.handler.on_add_order(event)
+    }
+
+    fn on_update_order(&mut self, event: OrderEvent) {
+        self.handler.on_update_order(event)
+    }
+
+    fn on_execute_order(&mut self, event: ExecutionEvent) {
+        self.handler.on_execute_order(event)
+    }
+
+    fn on_trading_status_change(&mut self, order_book: &OrderBook, previous: TradingState) {
+        self.handler.on_trading_status_change(order_book, previous)
+    }
+
+    fn on_imbalance(&mut self, order_book: &OrderBook, previous: Option<Imbalance>) {
+        self.handler.on_imbalance(order_book, previous)
+    }
+
+    fn on_broken_trade(&mut self, order_book: &OrderBook, trade: &Trade) {
+        self.handler.on_broken_trade(order_book, trade)
+    }
+
+    fn on_reference_data(&mut self, order_book: &OrderBook, update: ReferenceUpdate) {
+        self.handler.on_reference_data(order_book, update)
+    }
+
+    fn on_circuit_breaker(&mut self, circuit_breaker: &CircuitBreaker) {
+        self.handler.on_circuit_breaker(circuit_breaker)
+    }
+
+    fn on_gap_open(&mut self, from: u64, to: u64) {
+        self.handler.on_gap_open(from, to)
+    }
+
+    fn on_gap_close(&mut self, from: u64, to: u64, recovered: bool) {
+        self.handler.on_gap_close(from, to, recovered)
+    }
+
+    fn on_message_error(&mut self, message: &[u8], error: &ErrorCode) {
+        self.handler.on_message_error(message, error)
+    }
+
+    fn on_delete_order(&mut self, event: OrderEvent) {
+        self.deleted.push((event.order.id, event.order.average_fill_price(), false));
+        self.handler.on_delete_order(event)
+    }
+
+    fn on_delete_unmatched_order(&mut self, event: OrderEvent) {
+        self.deleted.push((event.order.id, event.order.average_fill_price(), true));
+        self.handler.on_delete_unmatched_order(event)
+    }
+}
+
+#[test]
+fn market_order_sweeps_report_the_average_fill_price() {
+    let mut manager = MarketManager::new(Fills::default());
+    let symbol = Symbol::new(SYMBOL, "TEST");
+    manager.add_symbol(symbol.clone()).unwrap();
+    manager.add_order_book(&symbol).unwrap();
+    manager.enable_matching().unwrap();
+    for (id, price, quantity) in [(1, 100, 10), (2, 102, 10), (3, 110, 10), (4, 110, 20)] {
+        manager.add_order(limit(id, OrderSide::Sell, price, quantity)).unwrap();
+    }
+
+    // The sweep stops at the slippage bound and the rest of the order is cancelled
+    manager.add_order(market(10, OrderSide::Buy, 30, 2)).unwrap();
+    assert!(manager.handler.deleted.contains(&(10, Some(101), true)));
+
+    // A sweep without a bound is filled entirely
+    manager.add_order(market(11, OrderSide::Buy, 20, u64::MAX)).unwrap();
+    assert!(manager.handler.deleted.contains(&(11, Some(110), false)));
+}
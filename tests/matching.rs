@@ -0,0 +1,97 @@
+mod common;
+
+use common::*;
+use itch_plus::orders::order::{ErrorCode, OrderSide, TimeInForce};
+
+#[test]
+fn limit_order_sweeps_several_levels() {
+    let mut manager = manager();
+    add_asks(&mut manager, &[(1, 100, 10), (2, 101, 10), (3, 102, 10)]);
+
+    manager.add_order(limit(10, OrderSide::Buy, 101, 25)).unwrap();
+
+    // The remainder rests at the limit price once the crossed levels are gone
+    let order = manager.get_order(10).unwrap();
+    assert_eq!((order.executed_quantity, order.leaves_quantity), (20, 5));
+    assert!(manager.get_order(1).is_err() && manager.get_order(2).is_err());
+    assert_eq!(manager.get_order(3).unwrap().leaves_quantity, 10);
+
+    // The resting bid at 101 no longer crosses the book
+    manager.add_order(limit(4, OrderSide::Sell, 102, 10)).unwrap();
+    assert_eq!(manager.get_order(10).unwrap().leaves_quantity, 5);
+}
+
+#[test]
+fn orders_at_the_same_price_fill_in_arrival_order() {
+    let mut manager = manager();
+    add_asks(&mut manager, &[(1, 100, 10), (2, 100, 10), (3, 100, 10)]);
+
+    manager.add_order(limit(10, OrderSide::Buy, 100, 15)).unwrap();
+    assert!(manager.get_order(1).is_err());
+    assert_eq!(manager.get_order(2).unwrap().leaves_quantity, 5);
+    assert_eq!(manager.get_order(3).unwrap().leaves_quantity, 10);
+    assert!(manager.get_order(10).is_err());
+}
+
+#[test]
+fn fill_or_kill_is_rejected_unless_fully_filled() {
+    let mut manager = manager();
+    add_asks(&mut manager, &[(1, 100, 10), (2, 101, 10)]);
+
+    // Not enough volume up to the limit price, nothing trades
+    manager.add_order(with_time_in_force(limit(10, OrderSide::Buy, 101, 25), TimeInForce::FOK)).unwrap();
+    assert!(manager.get_order(10).is_err());
+    assert_eq!(manager.get_order(1).unwrap().leaves_quantity, 10);
+    assert_eq!(manager.get_order(2).unwrap().leaves_quantity, 10);
+    assert_eq!(manager.handler.execute_orders(), 0);
+
+    manager.add_order(with_time_in_force(limit(11, OrderSide::Buy, 101, 15), TimeInForce::FOK)).unwrap();
+    assert!(manager.get_order(11).is_err());
+    assert!(manager.get_order(1).is_err());
+    assert_eq!(manager.get_order(2).unwrap().leaves_quantity, 5);
+}
+
+#[test]
+fn all_or_none_rests_until_it_can_be_filled() {
+    let mut manager = manager();
+    add_asks(&mut manager, &[(1, 100, 10), (2, 101, 10)]);
+
+    manager.add_order(with_time_in_force(limit(10, OrderSide::Buy, 101, 25), TimeInForce::AON)).unwrap();
+    assert_eq!(manager.handler.execute_orders(), 0);
+    assert_eq!(manager.get_order(10).unwrap().leaves_quantity, 25);
+
+    // New liquidity completes the chain and the whole order trades at once
+    manager.add_order(limit(3, OrderSide::Sell, 101, 5)).unwrap();
+    assert!((1..=3).chain([10]).all(|id| manager.get_order(id).is_err()));
+}
+
+#[test]
+fn duplicate_orders_are_rejected_before_matching() {
+    let mut manager = manager();
+    add_asks(&mut manager, &[(1, 100, 10), (2, 101, 10)]);
+
+    // The crossing order reuses the id of a resting one and must not trade
+    assert!(matches!(manager.add_order(limit(2, OrderSide::Buy, 100, 10)), Err(ErrorCode::OrderDuplicate)));
+    assert_eq!(manager.get_order(1).unwrap().leaves_quantity, 10);
+    assert_eq!(manager.handler.execute_orders(), 0);
+    assert_eq!(manager.handler.add_orders(), 2);
+}
+
+#[test]
+fn oversized_all_or_none_makers_are_skipped_across_levels() {
+    let mut manager = manager();
+    manager.add_order(with_time_in_force(limit(1, OrderSide::Sell, 100, 50), TimeInForce::AON)).unwrap();
+    add_asks(&mut manager, &[(2, 100, 10), (3, 101, 10)]);
+
+    // The 'All-Or-None' maker in front is too large, the chain continues behind it
+    manager.add_order(with_time_in_force(limit(10, OrderSide::Buy, 101, 15), TimeInForce::FOK)).unwrap();
+    assert!(manager.get_order(10).is_err() && manager.get_order(2).is_err());
+    assert_eq!(manager.get_order(3).unwrap().leaves_quantity, 5);
+    assert_eq!(manager.get_order(1).unwrap().leaves_quantity, 50);
+
+    add_asks(&mut manager, &[(4, 102, 10)]);
+    manager.add_order(limit(11, OrderSide::Buy, 102, 10)).unwrap();
+    assert!(manager.get_order(11).is_err() && manager.get_order(3).is_err());
+    assert_eq!(manager.get_order(4).unwrap().leaves_quantity, 5);
+    assert_eq!(manager.get_order(1).unwrap().leaves_quantity, 50);
+}
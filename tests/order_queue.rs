@@ -0,0 +1,49 @@
+mod common;
+
+use common::*;
+use itch_plus::{
+    levels::order_queue::OrderList,
+    orders::{order::{ErrorCode, OrderSide}, order_pool::OrderPool},
+};
+
+#[test]
+fn cancelled_orders_leave_the_queue_in_time_priority() {
+    let mut manager = manager();
+    add_asks(&mut manager, &[(1, 100, 10), (2, 100, 10), (3, 100, 10), (4, 100, 10), (5, 100, 10)]);
+
+    // Unlink the head, an inner order and the tail of the level queue
+    manager.delete_order(1).unwrap();
+    manager.delete_order(3).unwrap();
+    manager.delete_order(5).unwrap();
+
+    let level = manager.get_order_book(SYMBOL).unwrap().best_ask().unwrap();
+    assert_eq!(level.total_volume, 20);
+
+    // The remaining orders still fill in arrival order
+    manager.add_order(limit(10, OrderSide::Buy, 100, 15)).unwrap();
+    assert!(manager.get_order(2).is_err());
+    assert_eq!(manager.get_order(4).unwrap().leaves_quantity, 5);
+
+    // Orders queued after the cancellations go to the back
+    add_asks(&mut manager, &[(6, 100, 10)]);
+    manager.add_order(limit(11, OrderSide::Buy, 100, 10)).unwrap();
+    assert!(manager.get_order(4).is_err());
+    assert_eq!(manager.get_order(6).unwrap().leaves_quantity, 5);
+}
+
+#[test]
+fn orders_are_queued_in_one_level_at_most() {
+    let mut pool = OrderPool::default();
+    let handle = pool.allocate(limit(1, OrderSide::Sell, 100, 10));
+    let (mut first, mut second) = (OrderList::default(), OrderList::default());
+    first.push_back(&mut pool, handle).unwrap();
+
+    // The only order of a level has no links, yet it cannot join another level
+    assert!(matches!(second.push_back(&mut pool, handle), Err(ErrorCode::OrderDuplicate)));
+    assert!(matches!(second.remove(&mut pool, handle), Err(ErrorCode::OrderNotFound)));
+    assert_eq!((first.len(), second.len()), (1, 0));
+
+    first.remove(&mut pool, handle).unwrap();
+    second.push_back(&mut pool, handle).unwrap();
+    assert_eq!((first.front(), second.front()), (None, Some(handle)));
+}
@@ -0,0 +1,49 @@
+mod common;
+
+use common::*;
+use itch_plus::{itch_handler::ITCHHandler, market_executors::market_manager::MarketManager, market_handler::MarketHandler};
+
+const STOCK: &[u8; 8] = b"AAPL    ";
+
+fn add_order_with_mpid(stock_locate: u16, timestamp: u64, id: u64, side: u8, shares: u32, price: u32, mpid: &[u8; 4]) -> Vec<u8> {
+    let mut message = add_order(stock_locate, timestamp, id, side, shares, STOCK, price);
+    message[0] = b'F';
+    message.extend_from_slice(mpid);
+    message
+}
+
+#[test]
+fn displayed_depth_is_aggregated_per_mpid() {
+    let mut handler = ITCHHandler::new(MarketManager::<MarketHandler>::default());
+    let messages = [
+        stock_directory(1, STOCK),
+        add_order_with_mpid(1, 1, 1, b'B', 300, 100_000, b"ABCD"),
+        add_order_with_mpid(1, 2, 2, b'B', 200, 100_000, b"ABCD"),
+        add_order_with_mpid(1, 3, 3, b'B', 100, 99_900, b"ABCD"),
+        add_order_with_mpid(1, 4, 4, b'S', 100, 101_000, b"WXYZ"),
+        // Unattributed orders only count in the book depth
+        add_order(1, 5, 5, b'B', 500, STOCK, 100_000),
+    ];
+    for message in &messages {
+        handler.process_message(message).unwrap();
+    }
+
+    let order_book = handler.manager.get_order_book(1).unwrap();
+    let depth = order_book.participant_depth(b"ABCD").unwrap();
+    assert_eq!(depth.bids().collect::<Vec<_>>(), vec![(100_000, 500), (99_900, 100)]);
+    assert_eq!(depth.asks().count(), 0);
+    assert_eq!(order_book.participant_depth(b"WXYZ").unwrap().asks().collect::<Vec<_>>(), vec![(101_000, 100)]);
+    assert_eq!(order_book.participants().count(), 2);
+    assert_eq!(order_book.best_bid().unwrap().total_volume, 1000);
+
+    handler.process_message(&order_executed(1, 6, 1, 100, 1)).unwrap();
+    handler.process_message(&order_delete(1, 7, 3)).unwrap();
+    handler.process_message(&order_delete(1, 8, 4)).unwrap();
+
+    // Executed shares leave the depth and empty participants are dropped
+    let order_book = handler.manager.get_order_book(1).unwrap();
+    let depth = order_book.participant_depth(b"ABCD").unwrap();
+    assert_eq!(depth.bids().collect::<Vec<_>>(), vec![(100_000, 400)]);
+    assert!(order_book.participant_depth(b"WXYZ").is_none());
+    assert_eq!(order_book.participants().count(), 1);
+}
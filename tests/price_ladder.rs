@@ -0,0 +1,81 @@
+mod common;
+
+use common::*;
+use itch_plus::{
+    levels::{indexing::{LevelContainer, LevelIndex}, ladder::PriceLadder, level::{Level, LevelType}},
+    market_executors::market_manager::MarketManager,
+    market_handler::MarketHandler,
+    orders::order::{ErrorCode, Order, OrderSide, OrderType, TimeInForce},
+    symbols::symbol::Symbol,
+};
+
+fn prices(ladder: &PriceLadder) -> Vec<u64> {
+    ladder.iter().map(|level| level.price).collect()
+}
+
+#[test]
+fn far_quotes_do_not_grow_the_window() {
+    let mut asks = PriceLadder::new(LevelType::Ask, 100, 1024);
+    let capacity = asks.capacity();
+    asks.insert(Level::with_price(LevelType::Ask, 1_000_000)).unwrap();
+    asks.insert(Level::with_price(LevelType::Ask, 1_000_100)).unwrap();
+
+    // A deep outlier is kept out of the window
+    let outlier = asks.insert(Level::with_price(LevelType::Ask, 2_000_000_000)).unwrap();
+    assert_eq!(asks.capacity(), capacity);
+    assert_eq!(asks.overflow_len(), 1);
+    assert_eq!(prices(&asks), vec![1_000_000, 1_000_100, 2_000_000_000]);
+    assert_eq!(asks.get(outlier).unwrap().price, 2_000_000_000);
+
+    asks.remove(outlier).unwrap();
+    assert_eq!(asks.overflow_len(), 0);
+    assert_eq!(prices(&asks), vec![1_000_000, 1_000_100]);
+}
+
+#[test]
+fn far_best_quotes_move_the_window() {
+    let mut bids = PriceLadder::new(LevelType::Bid, 100, 1024);
+    let capacity = bids.capacity();
+    bids.insert(Level::with_price(LevelType::Bid, 1_000_000)).unwrap();
+    bids.insert(Level::with_price(LevelType::Bid, 999_900)).unwrap();
+
+    // The window follows the best level and the previous inside overflows
+    let outlier = bids.insert(Level::with_price(LevelType::Bid, 2_000_000_000)).unwrap();
+    assert_eq!(bids.capacity(), capacity);
+    assert_eq!(bids.overflow_len(), 2);
+    assert_eq!(prices(&bids), vec![2_000_000_000, 1_000_000, 999_900]);
+
+    // Once the outlier leaves, the window is rebased on the next best level
+    bids.remove(outlier).unwrap();
+    assert_eq!(bids.capacity(), capacity);
+    assert_eq!(bids.overflow_len(), 0);
+    assert!(bids.base() <= 999_900 && 1_000_000 < bids.base() + capacity as u64 * 100);
+    assert_eq!(bids.get(bids.best().unwrap()).unwrap().price, 1_000_000);
+    assert_eq!(prices(&bids), vec![1_000_000, 999_900]);
+}
+
+#[test]
+fn off_tick_orders_are_rejected_before_they_are_stored() {
+    let mut manager = MarketManager::new(MarketHandler::new());
+    let symbol = Symbol::new(SYMBOL, "TEST");
+    manager.add_symbol(symbol.clone()).unwrap();
+    manager.add_order_book_with_levels(&symbol, LevelContainer::Ladder { tick_size: 100, capacity: 64 }).unwrap();
+    manager.enable_matching().unwrap();
+
+    let order = Order { time_in_force: TimeInForce::GTD(1_000), ..limit(1, OrderSide::Buy, 10_050, 10) };
+    assert!(matches!(manager.add_order(order), Err(ErrorCode::LevelPriceInvalid)));
+    let order = Order { order_type: OrderType::StopLimit, stop_price: 10_100, ..limit(2, OrderSide::Buy, 10_150, 10) };
+    assert!(matches!(manager.add_order(order), Err(ErrorCode::LevelPriceInvalid)));
+
+    // Nothing of the rejected orders is left behind
+    assert!(manager.get_order(1).is_err() && manager.get_order(2).is_err());
+    assert_eq!(manager.handler.add_orders(), 0);
+    assert!(manager.get_order_book(SYMBOL).unwrap().best_bid().is_none());
+
+    // Replacing onto an off-tick price keeps the previous order
+    manager.add_order(limit(1, OrderSide::Buy, 10_000, 10)).unwrap();
+    assert!(matches!(manager.replace_order(1, 3, 10_050, 10), Err(ErrorCode::LevelPriceInvalid)));
+    assert!(manager.get_order(3).is_err());
+    assert_eq!(manager.get_order(1).unwrap().price, 10_000);
+    assert_eq!(manager.get_order_book(SYMBOL).unwrap().best_bid().unwrap().total_volume, 10);
+}
@@ -0,0 +1,100 @@
+mod common;
+
+use common::*;
+use itch_plus::{
+    itch_handler::ITCHHandler,
+    market_executors::market_manager::MarketManager,
+    market_handler::MarketHandler,
+    order_book::reference_data::{AuctionCollar, MarketMakerMode, ParticipantState},
+};
+
+const STOCK: &[u8; 8] = b"AAPL    ";
+
+fn handler() -> ITCHHandler<MarketHandler> {
+    let mut handler = ITCHHandler::new(MarketManager::<MarketHandler>::default());
+    handler.process_message(&stock_directory(1, STOCK)).unwrap();
+    handler
+}
+
+fn participant_position(stock_locate: u16, mpid: &[u8; 4], primary: u8, mode: u8, state: u8) -> Vec<u8> {
+    let mut message = header(b'L', stock_locate, 0);
+    message.extend_from_slice(mpid);
+    message.extend_from_slice(STOCK);
+    message.extend_from_slice(&[primary, mode, state]);
+    message
+}
+
+fn auction_collar(stock_locate: u16, reference: u32, upper: u32, lower: u32, extension: u32) -> Vec<u8> {
+    let mut message = header(b'J', stock_locate, 0);
+    message.extend_from_slice(STOCK);
+    for value in [reference, upper, lower, extension] {
+        message.extend_from_slice(&value.to_be_bytes());
+    }
+    message
+}
+
+fn mwcb_decline(levels: [u64; 3]) -> Vec<u8> {
+    let mut message = header(b'V', 0, 0);
+    for level in levels {
+        message.extend_from_slice(&level.to_be_bytes());
+    }
+    message
+}
+
+fn mwcb_status(level: u8) -> Vec<u8> {
+    let mut message = header(b'W', 0, 0);
+    message.push(level);
+    message
+}
+
+#[test]
+fn reference_messages_update_the_symbol_reference() {
+    let mut handler = handler();
+    let updates = handler.manager.handler.updates();
+    handler.process_message(&participant_position(1, b"ABCD", b'Y', b'N', b'A')).unwrap();
+    handler.process_message(&participant_position(1, b"WXYZ", b'N', b'P', b'S')).unwrap();
+    handler.process_message(&auction_collar(1, 100_000, 105_000, 95_000, 1)).unwrap();
+
+    let reference = &handler.manager.get_order_book(1).unwrap().reference;
+    let position = reference.participants[b"ABCD"];
+    assert!(position.primary_market_maker);
+    assert_eq!((position.market_maker_mode, position.participant_state), (MarketMakerMode::Normal, ParticipantState::Active));
+    let position = reference.participants[b"WXYZ"];
+    assert!(!position.primary_market_maker);
+    assert_eq!((position.market_maker_mode, position.participant_state), (MarketMakerMode::Passive, ParticipantState::Suspended));
+    assert_eq!(
+        reference.auction_collar,
+        Some(AuctionCollar { reference_price: 100_000, upper_price: 105_000, lower_price: 95_000, extension: 1 })
+    );
+    assert!(reference.ipo_quoting_period.is_none() && reference.price_discovery.is_none());
+
+    // A later position of the same participant replaces the previous one
+    handler.process_message(&participant_position(1, b"ABCD", b'N', b'L', b'W')).unwrap();
+    let reference = &handler.manager.get_order_book(1).unwrap().reference;
+    assert_eq!(reference.participants.len(), 2);
+    assert_eq!(reference.participants[b"ABCD"].participant_state, ParticipantState::Withdrawn);
+    assert_eq!(handler.manager.handler.updates() - updates, 4);
+
+    // Reference data of an unknown symbol is rejected
+    assert!(handler.process_message(&auction_collar(2, 100_000, 105_000, 95_000, 0)).is_err());
+}
+
+#[test]
+fn circuit_breaker_tracks_declines_and_breaches() {
+    let mut handler = handler();
+    let updates = handler.manager.handler.updates();
+    handler.process_message(&mwcb_decline([3_900_000_000, 3_600_000_000, 3_200_000_000])).unwrap();
+
+    let circuit_breaker = handler.manager.order_books.circuit_breaker();
+    assert_eq!(circuit_breaker.decline_levels, Some([3_900_000_000, 3_600_000_000, 3_200_000_000]));
+    assert_eq!(circuit_breaker.breached_level, None);
+
+    handler.process_message(&mwcb_status(b'1')).unwrap();
+    handler.process_message(&mwcb_status(b'2')).unwrap();
+    assert_eq!(handler.manager.order_books.circuit_breaker().breached_level, Some(2));
+
+    // Unknown breach codes leave the state untouched
+    handler.process_message(&mwcb_status(b'9')).unwrap();
+    assert_eq!(handler.manager.order_books.circuit_breaker().breached_level, Some(2));
+    assert_eq!(handler.manager.handler.updates() - updates, 4);
+}
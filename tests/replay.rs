@@ -0,0 +1,109 @@
+mod common;
+
+use common::*;
+use itch_plus::{
+    itch_handler::ITCHHandler,
+    market_executors::market_manager::MarketManager,
+    market_handler::MarketHandler,
+    replay::sharded_replay::ShardedReplay,
+};
+
+const STOCKS: [&[u8; 8]; 3] = [b"AAPL    ", b"MSFT    ", b"NVDA    "];
+
+fn stock_locate(id: u64) -> u16 {
+    (id % 3) as u16 + 1
+}
+
+// A session of adds, partial executions and deletes spread over three symbols
+fn session() -> (Vec<Vec<u8>>, Vec<u64>) {
+    let mut messages = Vec::new();
+    let mut ids = Vec::new();
+    for (index, stock) in STOCKS.iter().enumerate() {
+        messages.push(stock_directory(index as u16 + 1, stock));
+    }
+    for id in 1..=60u64 {
+        let stock_locate = stock_locate(id);
+        let side = if id % 2 == 0 { b'B' } else { b'S' };
+        let price = if side == b'B' { 100_000 - id as u32 * 100 } else { 101_000 + id as u32 * 100 };
+        messages.push(add_order(stock_locate, id, id, side, 100 + id as u32, STOCKS[stock_locate as usize - 1], price));
+        ids.push(id);
+    }
+    for id in (1..=60u64).step_by(4) {
+        messages.push(order_executed(stock_locate(id), 100 + id, id, 50, id));
+        messages.push(order_delete(stock_locate(id + 1), 100 + id, id + 1));
+    }
+    (messages, ids)
+}
+
+fn resting(manager: &MarketManager<MarketHandler>, ids: &[u64]) -> Vec<Option<(u64, u64)>> {
+    ids.iter()
+        .map(|&id| manager.get_order(id).ok().map(|order| (order.price, order.leaves_quantity)))
+        .collect()
+}
+
+#[test]
+fn sharded_replay_matches_a_single_threaded_replay() {
+    let (messages, ids) = session();
+    let data = frame(&messages);
+
+    let mut handler = ITCHHandler::new(MarketManager::<MarketHandler>::default());
+    handler.process_buffer(&data).unwrap();
+    let expected = resting(&handler.manager, &ids);
+
+    for workers in [1, 2, 3, 4] {
+        let replay = ShardedReplay::new(workers).with_batch_size(7).with_queue_depth(2);
+        let shards = replay
+            .run::<MarketHandler, _, _>(&data, |_, handler| (resting(&handler.manager, &ids), handler.manager.order_books.len()))
+            .unwrap();
+        assert_eq!(shards.len(), workers);
+
+        // Every order lives in exactly one shard, the one owning its stock locate
+        let mut merged = vec![None; ids.len()];
+        for (shard, (orders, _)) in shards.iter().enumerate() {
+            for (index, order) in orders.iter().enumerate() {
+                if order.is_some() {
+                    assert_eq!(replay.shard_of(stock_locate(ids[index])), shard);
+                    assert!(merged[index].is_none());
+                    merged[index] = *order;
+                }
+            }
+        }
+        assert_eq!(merged, expected);
+        assert_eq!(shards.iter().map(|(_, books)| books).sum::<usize>(), STOCKS.len());
+    }
+}
+
+#[test]
+fn sharded_replay_reports_truncated_files() {
+    let (messages, _) = session();
+    let mut data = frame(&messages);
+    data.truncate(data.len() - 1);
+
+    let error = ShardedReplay::new(2)
+        .run::<MarketHandler, _, _>(&data, |_, _| ())
+        .unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+}
+
+#[test]
+fn replay_skips_messages_that_cannot_be_applied() {
+    let stock = STOCKS[0];
+    let data = frame(&[
+        stock_directory(1, stock),
+        // Neither an execution of an unknown order nor an unknown message type stop the replay
+        order_executed(1, 1, 99, 10, 1),
+        header(b'?', 1, 2),
+        add_order(1, 3, 1, b'B', 100, stock, 10_000),
+    ]);
+    let mut handler = ITCHHandler::new(MarketManager::<MarketHandler>::default());
+    handler.process_buffer(&data).unwrap();
+    assert!(handler.manager.get_order(1).is_ok());
+    assert_eq!(handler.manager.handler.message_errors(), 2);
+
+    // A message whose size does not match its type stops the replay
+    let mut truncated = add_order(1, 4, 2, b'S', 100, stock, 10_100);
+    truncated.pop();
+    let data = frame(&[truncated, add_order(1, 5, 3, b'S', 100, stock, 10_200)]);
+    assert_eq!(handler.process_buffer(&data).unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+    assert!(handler.manager.get_order(3).is_err());
+}
@@ -0,0 +1,354 @@
+mod common;
+
+use std::net::{Shutdown, UdpSocket};
+
+use common::*;
+use itch_plus::{
+    itch_handler::ITCHHandler,
+    market_handler::MarketHandler,
+    orders::order::ErrorCode,
+    replay::mapped_file::MappedITCHFile,
+    session::{
+        loopback::{TcpLoopback, UdpLoopback},
+        mold_udp64::{MoldUDP64Packet, MoldUDP64Session},
+        recovery::{FileRewind, MoldUDP64RequestServer, NoRecovery, RecoverySource},
+        sequencer::{Gap, Sequencer},
+        session_handler::SessionHandler,
+        soup_bin_tcp::{SoupBinTCPPacket, SoupBinTCPSession},
+    },
+};
+
+const SESSION: [u8; 10] = *b"SESSION001";
+
+// Session handler recording everything the decoder reports
+#[derive(Default)]
+struct Recorder {
+    messages: Vec<(u64, Vec<u8>)>,
+    gaps: Vec<(u64, u64)>,
+    duplicates: Vec<u64>,
+    ended: bool,
+}
+
+impl Recorder {
+    fn sequences(&self) -> Vec<u64> {
+        self.messages.iter().map(|(sequence, _)| *sequence).collect()
+    }
+}
+
+impl SessionHandler for Recorder {
+    fn on_message(&mut self, sequence: u64, message: &[u8]) -> Result<(), ErrorCode> {
+        self.messages.push((sequence, message.to_vec()));
+        Ok(())
+    }
+
+    fn on_gap(&mut self, expected: u64, received: u64) -> Result<(), ErrorCode> {
+        self.gaps.push((expected, received));
+        Ok(())
+    }
+
+    fn on_duplicate(&mut self, sequence: u64) {
+        self.duplicates.push(sequence);
+    }
+
+    fn on_end_of_session(&mut self) {
+        self.ended = true;
+    }
+}
+
+// Retransmission service keeping the requested ranges. Stored messages are
+// answered immediately, the others are delivered later by the test.
+#[derive(Default)]
+struct Retransmitter {
+    requests: Vec<(u64, u64)>,
+    messages: Vec<(u64, Vec<u8>)>,
+}
+
+impl RecoverySource for Retransmitter {
+    fn request(&mut self, from: u64, to: u64, recovered: &mut Vec<(u64, Vec<u8>)>) -> Result<(), ErrorCode> {
+        self.requests.push((from, to));
+        recovered.extend(self.messages.iter().filter(|(sequence, _)| (from..to).contains(sequence)).cloned());
+        Ok(())
+    }
+}
+
+fn stock(stock_locate: u16) -> [u8; 8] {
+    let mut stock = *b"S       ";
+    let digits = stock_locate.to_string();
+    stock[1..1 + digits.len()].copy_from_slice(digits.as_bytes());
+    stock
+}
+
+fn directory(stock_locate: u16) -> Vec<u8> {
+    stock_directory(stock_locate, &stock(stock_locate))
+}
+
+fn sequencer(messages: Vec<(u64, Vec<u8>)>) -> Sequencer<MarketHandler, Retransmitter> {
+    Sequencer::new(ITCHHandler::new(Default::default()), Retransmitter { requests: Vec::new(), messages })
+}
+
+#[test]
+fn mold_udp64_reports_gaps_and_duplicates() {
+    let loopback = UdpLoopback::new().unwrap();
+    loopback.send(&MoldUDP64Packet::encode(&SESSION, 1, &[b"a", b"bb"])).unwrap();
+    loopback.send(&MoldUDP64Packet::encode(&SESSION, 2, &[b"bb", b"c"])).unwrap();
+    loopback.send(&MoldUDP64Packet::encode(&SESSION, 6, &[b"f"])).unwrap();
+    // Heartbeats announce the next sequence number
+    loopback.send(&MoldUDP64Packet::encode(&SESSION, 9, &[])).unwrap();
+    loopback.send(&MoldUDP64Packet::encode_end_of_session(&SESSION, 9)).unwrap();
+
+    let mut session = MoldUDP64Session::new();
+    let mut recorder = Recorder::default();
+    while session.receive(&loopback.receiver, &mut recorder).unwrap() {}
+
+    assert_eq!(recorder.sequences(), vec![1, 2, 3, 6]);
+    assert_eq!(recorder.messages[2].1, b"c");
+    assert_eq!(recorder.gaps, vec![(4, 6), (7, 9)]);
+    assert_eq!(recorder.duplicates, vec![2]);
+    assert!(recorder.ended && session.is_ended());
+
+    // Packets of another session are rejected
+    assert!(session.process_packet(&MoldUDP64Packet::encode(b"OTHER00000", 9, &[]), &mut recorder).is_err());
+}
+
+#[test]
+fn soup_bin_tcp_drops_replayed_messages_after_relogin() {
+    let mut stream = Vec::new();
+    stream.extend(SoupBinTCPPacket::LoginAccepted { session: SESSION, sequence_number: 5 }.encode());
+    stream.extend(SoupBinTCPPacket::SequencedData(b"x").encode());
+    stream.extend(SoupBinTCPPacket::ServerHeartbeat.encode());
+    stream.extend(SoupBinTCPPacket::SequencedData(b"yy").encode());
+    // The server replays from 6 after a reconnect
+    stream.extend(SoupBinTCPPacket::LoginAccepted { session: SESSION, sequence_number: 6 }.encode());
+    stream.extend(SoupBinTCPPacket::SequencedData(b"yy").encode());
+    stream.extend(SoupBinTCPPacket::SequencedData(b"z").encode());
+    stream.extend(SoupBinTCPPacket::EndOfSession.encode());
+
+    let mut loopback = TcpLoopback::new().unwrap();
+    loopback.send(&stream).unwrap();
+    loopback.server.shutdown(Shutdown::Write).unwrap();
+
+    let mut session = SoupBinTCPSession::new();
+    let mut recorder = Recorder::default();
+    session.process(&loopback.client, &mut recorder).unwrap();
+
+    assert_eq!(recorder.sequences(), vec![5, 6, 7]);
+    assert_eq!(recorder.messages[2].1, b"z");
+    assert!(recorder.gaps.is_empty());
+    assert_eq!(recorder.duplicates, vec![6]);
+    assert!(recorder.ended && session.is_ended());
+}
+
+#[test]
+fn soup_bin_tcp_reassembles_split_packets() {
+    let mut stream = Vec::new();
+    stream.extend(SoupBinTCPPacket::LoginAccepted { session: SESSION, sequence_number: 1 }.encode());
+    stream.extend(SoupBinTCPPacket::SequencedData(b"first").encode());
+    stream.extend(SoupBinTCPPacket::SequencedData(b"second").encode());
+
+    for chunk in 1..stream.len() {
+        let mut session = SoupBinTCPSession::new();
+        let mut recorder = Recorder::default();
+        for data in stream.chunks(chunk) {
+            session.process_buffer(data, &mut recorder).unwrap();
+        }
+        assert_eq!(recorder.sequences(), vec![1, 2]);
+        assert_eq!(recorder.messages[1].1, b"second");
+    }
+}
+
+#[test]
+fn sequencer_recovers_gaps_from_the_mold_udp64_feed() {
+    let loopback = UdpLoopback::new().unwrap();
+    loopback.send(&MoldUDP64Packet::encode(&SESSION, 1, &[&directory(1)])).unwrap();
+    loopback.send(&MoldUDP64Packet::encode(&SESSION, 4, &[&directory(4)])).unwrap();
+    loopback.send(&MoldUDP64Packet::encode_end_of_session(&SESSION, 5)).unwrap();
+
+    // The retransmitter answers the request for the lost messages at once
+    let mut sequencer = sequencer((1..=4).map(|sequence| (sequence, directory(sequence as u16))).collect());
+    let mut session = MoldUDP64Session::new();
+    while session.receive(&loopback.receiver, &mut sequencer).unwrap() {}
+
+    assert_eq!(sequencer.recovery.requests, vec![(2, 4)]);
+    assert!(!sequencer.is_recovering());
+    assert_eq!(sequencer.expected_sequence_number(), Some(5));
+    assert_eq!(sequencer.handler.manager.order_books.len(), 4);
+    assert!(sequencer.handler.manager.order_books.values().all(|order_book| !order_book.stale));
+}
+
+#[test]
+fn sequencer_keeps_every_book_stale_until_the_gap_is_filled() {
+    let mut sequencer = sequencer(Vec::new());
+    sequencer.on_sequenced(1, &directory(1)).unwrap();
+    sequencer.on_sequenced(2, &directory(2)).unwrap();
+    // The only update of the second book is lost, the first one keeps trading
+    sequencer.on_sequenced(4, &add_order(1, 4, 41, b'B', 100, &stock(1), 10_000)).unwrap();
+    sequencer.on_sequenced(5, &add_order(1, 5, 51, b'S', 100, &stock(1), 10_100)).unwrap();
+
+    assert_eq!(sequencer.recovery.requests, vec![(3, 4)]);
+    assert_eq!(sequencer.gap(), Some(Gap { from: 3, to: 4 }));
+    assert_eq!(sequencer.pending(), 2);
+    assert!(sequencer.handler.is_stale());
+    assert!(sequencer.handler.manager.order_books.values().all(|order_book| order_book.stale));
+
+    // Later messages keep waiting behind the gap
+    sequencer.on_sequenced(6, &directory(3)).unwrap();
+    assert_eq!(sequencer.pending(), 3);
+
+    sequencer.on_recovered(3, &add_order(2, 3, 31, b'B', 100, &stock(2), 20_000)).unwrap();
+    assert!(!sequencer.is_recovering());
+    assert!(!sequencer.handler.is_stale());
+    assert_eq!(sequencer.expected_sequence_number(), Some(7));
+    assert_eq!(sequencer.handler.manager.order_books.len(), 3);
+    assert!(sequencer.handler.manager.order_books.values().all(|order_book| !order_book.stale));
+    assert!([31, 41, 51].iter().all(|&id| sequencer.handler.manager.get_order(id).is_ok()));
+}
+
+// Retransmission service that cannot be reached
+struct Unreachable;
+
+impl RecoverySource for Unreachable {
+    fn request(&mut self, _from: u64, _to: u64, _recovered: &mut Vec<(u64, Vec<u8>)>) -> Result<(), ErrorCode> {
+        Err(ErrorCode::OtherError("retransmitter unreachable".to_string()))
+    }
+}
+
+#[test]
+fn failed_gap_requests_reach_the_session() {
+    let mut sequencer = Sequencer::new(ITCHHandler::<MarketHandler>::new(Default::default()), Unreachable);
+    let mut session = MoldUDP64Session::new();
+    session.process_packet(&MoldUDP64Packet::encode(&SESSION, 1, &[&directory(1)]), &mut sequencer).unwrap();
+
+    // A heartbeat announces messages that were never received
+    let error = session.process_packet(&MoldUDP64Packet::encode(&SESSION, 4, &[]), &mut sequencer).unwrap_err();
+    assert!(matches!(error, ErrorCode::OtherError(_)));
+
+    // The gap stays open and the books stale until it is recovered
+    assert_eq!(sequencer.gap(), Some(Gap { from: 2, to: 4 }));
+    assert!(sequencer.handler.is_stale());
+    assert!(sequencer.handler.manager.order_books.values().all(|order_book| order_book.stale));
+    assert_eq!((sequencer.handler.manager.handler.gaps(), sequencer.handler.manager.handler.recovered_gaps()), (1, 0));
+}
+
+// Live feed of the first directories in which the given sequence numbers are lost
+fn lossy_feed(lost: &[u64]) -> UdpLoopback {
+    let loopback = UdpLoopback::new().unwrap();
+    for sequence in (1..=4).filter(|sequence| !lost.contains(sequence)) {
+        loopback.send(&MoldUDP64Packet::encode(&SESSION, sequence, &[&directory(sequence as u16)])).unwrap();
+    }
+    loopback
+}
+
+#[test]
+fn sequencer_recovers_gaps_from_the_mold_udp64_request_server() {
+    let server = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let recovery = MoldUDP64RequestServer::new(UdpSocket::bind("127.0.0.1:0").unwrap(), server.local_addr().unwrap(), SESSION).unwrap();
+    let responses = recovery.socket().try_clone().unwrap();
+    let mut sequencer = Sequencer::new(ITCHHandler::<MarketHandler>::new(Default::default()), recovery);
+
+    let feed = lossy_feed(&[2, 3]);
+    let mut session = MoldUDP64Session::new();
+    session.receive(&feed.receiver, &mut sequencer).unwrap();
+    session.receive(&feed.receiver, &mut sequencer).unwrap();
+    assert_eq!(sequencer.gap(), Some(Gap { from: 2, to: 4 }));
+    assert!(sequencer.handler.is_stale());
+
+    // The server gets the session, the first missing sequence number and the message count
+    let mut request = [0; 64];
+    let (size, client) = server.recv_from(&mut request).unwrap();
+    assert_eq!(&request[..size], [&SESSION[..], &2u64.to_be_bytes(), &2u16.to_be_bytes()].concat());
+
+    // Retransmissions come back behind the live feed and are not dropped as duplicates
+    server.send_to(&MoldUDP64Packet::encode(&SESSION, 2, &[&directory(2), &directory(3)]), client).unwrap();
+    session.receive(&responses, &mut sequencer).unwrap();
+    assert!(!sequencer.is_recovering());
+    assert!(!sequencer.handler.is_stale());
+    assert_eq!(sequencer.expected_sequence_number(), Some(5));
+    assert_eq!(sequencer.handler.manager.order_books.len(), 4);
+    assert_eq!((sequencer.handler.manager.handler.gaps(), sequencer.handler.manager.handler.recovered_gaps()), (1, 1));
+}
+
+#[test]
+fn sequencer_recovers_gaps_by_rewinding_the_file() {
+    let path = std::env::temp_dir().join(format!("itch_plus_rewind_{}.itch", std::process::id()));
+    std::fs::write(&path, frame(&(1..=4).map(directory).collect::<Vec<_>>())).unwrap();
+    let recovery = FileRewind::new(MappedITCHFile::open(&path).unwrap());
+    let mut sequencer = Sequencer::new(ITCHHandler::<MarketHandler>::new(Default::default()), recovery);
+
+    // The gap is opened and closed again while the message behind it is handled
+    let feed = lossy_feed(&[2, 3]);
+    let mut session = MoldUDP64Session::new();
+    session.receive(&feed.receiver, &mut sequencer).unwrap();
+    session.receive(&feed.receiver, &mut sequencer).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(!sequencer.is_recovering());
+    assert_eq!(sequencer.expected_sequence_number(), Some(5));
+    assert_eq!(sequencer.handler.manager.order_books.len(), 4);
+    assert!(sequencer.handler.manager.order_books.values().all(|order_book| !order_book.stale));
+    assert_eq!((sequencer.handler.manager.handler.gaps(), sequencer.handler.manager.handler.recovered_gaps()), (1, 1));
+}
+
+#[test]
+fn gaps_without_recovery_are_filled_by_packets_recovered_out_of_band() {
+    let mut sequencer = Sequencer::new(ITCHHandler::<MarketHandler>::new(Default::default()), NoRecovery);
+
+    let feed = lossy_feed(&[2, 3]);
+    let mut session = MoldUDP64Session::new();
+    session.receive(&feed.receiver, &mut sequencer).unwrap();
+    session.receive(&feed.receiver, &mut sequencer).unwrap();
+    assert_eq!(sequencer.gap(), Some(Gap { from: 2, to: 4 }));
+    assert_eq!(sequencer.pending(), 1);
+    assert!(sequencer.handler.manager.order_books.values().all(|order_book| order_book.stale));
+
+    // Packets recovered out of band close the gap and release the buffered message
+    sequencer.on_recovered_packet(&MoldUDP64Packet::encode(&SESSION, 2, &[&directory(2), &directory(3)])).unwrap();
+    assert!(!sequencer.is_recovering());
+    assert_eq!(sequencer.pending(), 0);
+    assert_eq!(sequencer.handler.manager.order_books.len(), 4);
+    assert!(sequencer.handler.manager.order_books.values().all(|order_book| !order_book.stale));
+    assert_eq!((sequencer.handler.manager.handler.gaps(), sequencer.handler.manager.handler.recovered_gaps()), (1, 1));
+}
+
+#[test]
+fn abandoned_gaps_apply_the_buffered_messages_and_leave_the_books_stale() {
+    let mut sequencer = Sequencer::new(ITCHHandler::<MarketHandler>::new(Default::default()), NoRecovery);
+    sequencer.on_sequenced(1, &directory(1)).unwrap();
+    sequencer.on_sequenced(3, &directory(3)).unwrap();
+    sequencer.on_sequenced(5, &directory(5)).unwrap();
+    assert_eq!(sequencer.gap(), Some(Gap { from: 2, to: 5 }));
+
+    sequencer.abandon_gap().unwrap();
+    assert!(!sequencer.is_recovering());
+    assert_eq!(sequencer.pending(), 0);
+    assert_eq!(sequencer.expected_sequence_number(), Some(6));
+    assert_eq!(sequencer.handler.manager.order_books.len(), 3);
+    assert!(sequencer.handler.manager.order_books.values().all(|order_book| order_book.stale));
+    let handler = &sequencer.handler.manager.handler;
+    assert_eq!((handler.gaps(), handler.recovered_gaps(), handler.abandoned_gaps()), (1, 0, 1));
+
+    // Late messages of the abandoned range are dropped and recovered gaps keep the books stale
+    sequencer.on_recovered(2, &directory(2)).unwrap();
+    sequencer.on_sequenced(7, &directory(7)).unwrap();
+    sequencer.on_recovered(6, &directory(6)).unwrap();
+    assert_eq!(sequencer.handler.manager.order_books.len(), 5);
+    assert!(sequencer.handler.is_stale());
+    assert!(sequencer.handler.manager.order_books.values().all(|order_book| order_book.stale));
+}
+
+#[test]
+fn gaps_are_abandoned_once_the_buffer_is_full() {
+    let mut sequencer = Sequencer::new(ITCHHandler::<MarketHandler>::new(Default::default()), NoRecovery).with_max_pending(2);
+    sequencer.on_sequenced(1, &directory(1)).unwrap();
+    sequencer.on_sequenced(3, &directory(3)).unwrap();
+    sequencer.on_sequenced(4, &directory(4)).unwrap();
+    assert_eq!(sequencer.pending(), 2);
+
+    // The next message past the gap cannot be buffered, so the gap is given up
+    sequencer.on_sequenced(5, &directory(5)).unwrap();
+    assert!(!sequencer.is_recovering());
+    assert_eq!(sequencer.pending(), 0);
+    assert_eq!(sequencer.expected_sequence_number(), Some(6));
+    assert_eq!(sequencer.handler.manager.order_books.len(), 4);
+    assert!(sequencer.handler.is_stale());
+    assert_eq!(sequencer.handler.manager.handler.abandoned_gaps(), 1);
+}
@@ -0,0 +1,60 @@
+mod common;
+
+use common::*;
+use itch_plus::orders::order::{Order, OrderSide, OrderType};
+
+fn stop(id: u64, side: OrderSide, stop_price: u64, quantity: u64) -> Order {
+    Order {
+        id,
+        symbol_id: SYMBOL,
+        order_type: OrderType::Stop,
+        order_side: side,
+        stop_price,
+        quantity,
+        leaves_quantity: quantity,
+        visible_quantity: quantity,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn stop_order_waits_for_a_trade_at_its_stop_price() {
+    let mut manager = manager();
+    manager.add_order(limit(1, OrderSide::Buy, 100, 10)).unwrap();
+    manager.add_order(limit(2, OrderSide::Sell, 105, 10)).unwrap();
+    manager.add_order(stop(20, OrderSide::Sell, 100, 10)).unwrap();
+
+    // The resting bid alone does not trigger the stop
+    assert_eq!(manager.get_order(20).unwrap().leaves_quantity, 10);
+    assert_eq!(manager.get_order(1).unwrap().leaves_quantity, 10);
+}
+
+#[test]
+fn triggered_stop_orders_cascade() {
+    let mut manager = manager();
+    manager.add_order(limit(1, OrderSide::Buy, 100, 10)).unwrap();
+    manager.add_order(limit(2, OrderSide::Buy, 99, 10)).unwrap();
+    manager.add_order(limit(3, OrderSide::Buy, 98, 10)).unwrap();
+    manager.add_order(stop(20, OrderSide::Sell, 100, 10)).unwrap();
+    manager.add_order(stop(21, OrderSide::Sell, 99, 10)).unwrap();
+
+    // A trade at 100 triggers the first stop, its fill at 99 triggers the second
+    manager.add_order(limit(10, OrderSide::Sell, 100, 10)).unwrap();
+    assert!([1, 2, 3, 20, 21].iter().all(|&id| manager.get_order(id).is_err()));
+    assert_eq!(manager.get_order_book(SYMBOL).unwrap().get_last_trade_price_ask(), 98);
+}
+
+#[test]
+fn replaced_stop_order_waits_for_its_new_stop_price() {
+    let mut manager = manager();
+    manager.add_order(limit(1, OrderSide::Buy, 100, 10)).unwrap();
+    manager.add_order(limit(2, OrderSide::Buy, 98, 10)).unwrap();
+    manager.add_order(stop(20, OrderSide::Sell, 100, 10)).unwrap();
+    manager.replace_order(20, 21, 98, 10).unwrap();
+    assert_eq!(manager.get_order(21).unwrap().stop_price, 98);
+
+    // A trade at the previous stop price no longer triggers the stop
+    manager.add_order(limit(10, OrderSide::Sell, 100, 10)).unwrap();
+    assert!(manager.get_order(1).is_err());
+    assert_eq!(manager.get_order(21).unwrap().leaves_quantity, 10);
+}
@@ -0,0 +1,77 @@
+mod common;
+
+use common::*;
+use itch_plus::orders::order::{ErrorCode, OrderSide, TimeInForce};
+
+#[test]
+fn day_and_good_till_date_orders_expire() {
+    let mut manager = manager();
+    manager.set_day_close(2_000).unwrap();
+    manager.add_order(with_time_in_force(limit(1, OrderSide::Buy, 100, 10), TimeInForce::GTD(1_000))).unwrap();
+    manager.add_order(with_time_in_force(limit(2, OrderSide::Buy, 99, 10), TimeInForce::DAY)).unwrap();
+    manager.add_order(limit(3, OrderSide::Buy, 98, 10)).unwrap();
+
+    manager.set_timestamp(999).unwrap();
+    assert!((1..=3).all(|id| manager.get_order(id).is_ok()));
+
+    manager.set_timestamp(1_000).unwrap();
+    assert!(manager.get_order(1).is_err());
+    assert!(manager.get_order(2).is_ok());
+
+    manager.set_timestamp(2_000).unwrap();
+    assert!(manager.get_order(2).is_err());
+    assert!(manager.get_order(3).is_ok());
+}
+
+#[test]
+fn good_till_date_requires_an_expiry() {
+    let mut manager = manager();
+    assert!(manager.add_order(with_time_in_force(limit(1, OrderSide::Buy, 100, 10), TimeInForce::GTD(0))).is_err());
+    assert!(manager.get_order(1).is_err());
+}
+
+#[test]
+fn good_till_date_in_the_past_is_rejected() {
+    let mut manager = manager();
+    manager.set_timestamp(1_000).unwrap();
+    for (id, expiry) in [(1, 999), (2, 1_000)] {
+        assert!(matches!(
+            manager.add_order(with_time_in_force(limit(id, OrderSide::Buy, 100, 10), TimeInForce::GTD(expiry))),
+            Err(ErrorCode::OrderParameterInvalid)
+        ));
+        assert!(manager.get_order(id).is_err());
+    }
+    manager.add_order(with_time_in_force(limit(3, OrderSide::Buy, 100, 10), TimeInForce::GTD(1_001))).unwrap();
+    assert!(manager.get_order(3).is_ok());
+}
+
+#[test]
+fn day_orders_follow_a_moved_close() {
+    let mut manager = manager();
+    manager.set_day_close(2_000).unwrap();
+    manager.add_order(with_time_in_force(limit(1, OrderSide::Buy, 100, 10), TimeInForce::DAY)).unwrap();
+    manager.add_order(with_time_in_force(limit(2, OrderSide::Buy, 99, 10), TimeInForce::GTD(2_000))).unwrap();
+
+    // A later close keeps the 'DAY' order alive past the old one
+    manager.set_day_close(3_000).unwrap();
+    manager.set_timestamp(2_000).unwrap();
+    assert!(manager.get_order(1).is_ok());
+    assert!(manager.get_order(2).is_err());
+
+    // An earlier close already reached expires it at once
+    manager.set_timestamp(2_500).unwrap();
+    manager.set_day_close(2_400).unwrap();
+    assert!(manager.get_order(1).is_err());
+}
+
+#[test]
+fn day_orders_after_the_close_are_rejected() {
+    let mut manager = manager();
+    manager.set_day_close(2_000).unwrap();
+    manager.set_timestamp(2_000).unwrap();
+    assert!(matches!(
+        manager.add_order(with_time_in_force(limit(1, OrderSide::Buy, 100, 10), TimeInForce::DAY)),
+        Err(ErrorCode::OrderParameterInvalid)
+    ));
+    assert!(manager.get_order(1).is_err());
+}
@@ -0,0 +1,30 @@
+mod common;
+
+use common::*;
+use itch_plus::{itch_handler::ITCHHandler, market_handler::MarketHandler};
+
+const STOCK: &[u8; 8] = b"AAPL    ";
+
+#[test]
+fn broken_trades_restore_the_last_trade_prices() {
+    let mut handler = ITCHHandler::<MarketHandler>::new(Default::default());
+    handler.process_message(&stock_directory(1, STOCK)).unwrap();
+    handler.process_message(&add_order(1, 1, 7, b'S', 100, STOCK, 500)).unwrap();
+    handler.process_message(&order_executed(1, 2, 7, 10, 11)).unwrap();
+    handler.process_message(&order_executed_with_price(1, 3, 7, 10, 12, 510)).unwrap();
+
+    let order_book = handler.manager.get_order_book(1).unwrap();
+    assert_eq!((order_book.get_last_trade_price_bid(), order_book.get_last_trade_price_ask()), (510, 510));
+
+    // The busted print no longer drives the stop orders
+    handler.process_message(&broken_trade(1, 4, 12)).unwrap();
+    let order_book = handler.manager.get_order_book(1).unwrap();
+    assert_eq!((order_book.get_last_trade_price_bid(), order_book.get_last_trade_price_ask()), (500, 500));
+    assert_eq!(order_book.statistics.last_price, Some(500));
+
+    // Without any trade left the prices are out of range again
+    handler.process_message(&broken_trade(1, 5, 11)).unwrap();
+    let order_book = handler.manager.get_order_book(1).unwrap();
+    assert_eq!((order_book.get_last_trade_price_bid(), order_book.get_last_trade_price_ask()), (0, u64::MAX));
+    assert_eq!(order_book.statistics.last_price, None);
+}
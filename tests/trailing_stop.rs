@@ -0,0 +1,82 @@
+mod common;
+
+use common::*;
+use itch_plus::{
+    market_executors::market_manager::MarketManager,
+    market_handler::MarketHandler,
+    orders::order::{Order, OrderSide, OrderType, TrailingOffset},
+};
+
+fn trailing_stop(id: u64, side: OrderSide, quantity: u64, distance: TrailingOffset, step: TrailingOffset) -> Order {
+    Order {
+        id,
+        symbol_id: SYMBOL,
+        order_type: OrderType::TrailingStop,
+        order_side: side,
+        quantity,
+        leaves_quantity: quantity,
+        visible_quantity: quantity,
+        trailing_distance: distance,
+        trailing_step: step,
+        ..Default::default()
+    }
+}
+
+// Prints a trade at the given price and leaves a bid resting there
+fn trade_at(manager: &mut MarketManager<MarketHandler>, id: u64, price: u64) {
+    manager.add_order(limit(id, OrderSide::Buy, price, 20)).unwrap();
+    manager.add_order(limit(id + 1, OrderSide::Sell, price, 10)).unwrap();
+}
+
+#[test]
+fn trailing_sell_stop_follows_a_rising_bid() {
+    let mut manager = manager();
+    trade_at(&mut manager, 1, 1_000);
+
+    manager.add_order(trailing_stop(20, OrderSide::Sell, 10, TrailingOffset::Ticks(50), TrailingOffset::Ticks(20))).unwrap();
+    assert_eq!(manager.get_order(20).unwrap().stop_price, 950);
+
+    // The bid moves up by more than the trailing step
+    trade_at(&mut manager, 3, 1_040);
+    assert_eq!(manager.get_order(20).unwrap().stop_price, 990);
+
+    // Moves shorter than the trailing step keep the stop price
+    trade_at(&mut manager, 5, 1_050);
+    assert_eq!(manager.get_order(20).unwrap().stop_price, 990);
+}
+
+#[test]
+fn trailing_offsets_in_basis_points_scale_with_the_price() {
+    let mut manager = manager();
+    trade_at(&mut manager, 1, 10_000);
+
+    // 100 basis points of 10000 are 100 ticks
+    manager.add_order(trailing_stop(20, OrderSide::Sell, 10, TrailingOffset::BasisPoints(100), TrailingOffset::BasisPoints(10))).unwrap();
+    assert_eq!(manager.get_order(20).unwrap().stop_price, 9_900);
+
+    trade_at(&mut manager, 3, 12_000);
+    assert_eq!(manager.get_order(20).unwrap().stop_price, 11_880);
+}
+
+#[test]
+fn trailing_distance_is_validated() {
+    let mut manager = manager();
+    let zero = trailing_stop(20, OrderSide::Sell, 10, TrailingOffset::Ticks(0), TrailingOffset::Ticks(0));
+    let wide_step = trailing_stop(21, OrderSide::Sell, 10, TrailingOffset::Ticks(10), TrailingOffset::Ticks(10));
+    let over_100_percent = trailing_stop(22, OrderSide::Sell, 10, TrailingOffset::BasisPoints(10_001), TrailingOffset::BasisPoints(0));
+    for order in [zero, wide_step, over_100_percent] {
+        assert!(manager.add_order(order).is_err());
+    }
+}
+
+#[test]
+fn trailing_stop_limit_moves_its_limit_price_without_wrapping() {
+    let mut manager = manager();
+    trade_at(&mut manager, 1, 1_000);
+
+    // A limit price beyond the signed range keeps its distance to the stop price
+    let order = Order { order_type: OrderType::TrailingStopLimit, price: u64::MAX - 100, ..trailing_stop(20, OrderSide::Sell, 10, TrailingOffset::Ticks(50), TrailingOffset::Ticks(20)) };
+    manager.add_order(order).unwrap();
+    let order = manager.get_order(20).unwrap();
+    assert_eq!((order.stop_price, order.price), (950, u64::MAX));
+}